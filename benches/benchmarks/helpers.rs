@@ -64,11 +64,11 @@ pub fn generate_dense_graph(vertices: usize, max_weight: f32, seed: u64) -> Grap
     let mut graph = vec![Vec::new(); vertices];
 
     // Create edges between all pairs of vertices
-    for from in 0..vertices {
+    for (from, edges) in graph.iter_mut().enumerate() {
         for to in 0..vertices {
             if from != to {
                 let weight = rng.random::<f32>() * max_weight;
-                graph[from].push(Edge::new(to, weight));
+                edges.push(Edge::new(to, weight));
             }
         }
     }
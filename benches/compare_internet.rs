@@ -1,4 +1,4 @@
-use bmssp::{ShortestPath, Graph, Edge};
+use bmssp::{dijkstra as dijkstra_with, dimacs, ContractionHierarchy, Coordinates, Edge, Graph, Metric, QueueKind, ShortestPath};
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::cmp::Ordering;
@@ -147,8 +147,8 @@ fn gen_graph(n: usize, m: usize, seed: u64) -> (Graph, DjGraph) {
     let mut dj: DjGraph = vec![Vec::new(); n];
 
     for _ in 0..m {
-        let u = rng.gen_range(0..n);
-        let mut v = rng.gen_range(0..n);
+        let u = rng.random_range(0..n);
+        let mut v = rng.random_range(0..n);
         if v == u {
             v = (v + 1) % n;
         }
@@ -159,6 +159,7 @@ fn gen_graph(n: usize, m: usize, seed: u64) -> (Graph, DjGraph) {
     (bm.into(), dj)
 }
 
+#[allow(clippy::single_element_loop)]
 fn compare_internet(c: &mut Criterion) {
     let mut group = c.benchmark_group("InternetTopologies");
     group.sample_size(10);
@@ -169,7 +170,7 @@ fn compare_internet(c: &mut Criterion) {
         let (bm_graph, dj_graph) = gen_graph(n, m, 42);
         group.bench_function(BenchmarkId::new("BMSSP", &label), |b| {
             b.iter(|| {
-                let mut sp = ShortestPath::new(bm_graph.clone());
+                let mut sp = ShortestPath::new(bm_graph.clone()).with_unit_weights(false);
                 black_box(sp.get(0usize))
             });
         });
@@ -207,6 +208,14 @@ fn compare_internet(c: &mut Criterion) {
         let (bm_graph, dj_graph) = load_as_edgelist(&path_str, undirected);
 
         group.bench_function(BenchmarkId::new("BMSSP", name), |b| {
+            b.iter(|| {
+                let mut sp = ShortestPath::new(bm_graph.clone()).with_unit_weights(false);
+                black_box(sp.get(0usize))
+            });
+        });
+
+        // unit weights are detected and answered by breadth-first search
+        group.bench_function(BenchmarkId::new("BMSSP_unit", name), |b| {
             b.iter(|| {
                 let mut sp = ShortestPath::new(bm_graph.clone());
                 black_box(sp.get(0usize))
@@ -246,6 +255,47 @@ fn compare_internet(c: &mut Criterion) {
         group.bench_function(BenchmarkId::new("Dijkstra", name), |b| {
             b.iter(|| black_box(dijkstra(&dj_graph, 1usize)));
         });
+
+        // point-to-point: full-field `get` versus bidirectional search
+        let target = bm_graph.len() - 1;
+        let mut sp = ShortestPath::new(bm_graph.clone());
+        group.bench_function(BenchmarkId::new("BMSSP_get_p2p", name), |b| {
+            b.iter(|| black_box(sp.get(1usize)[target]));
+        });
+        group.bench_function(BenchmarkId::new("Bidirectional_p2p", name), |b| {
+            b.iter(|| black_box(sp.bidirectional(1usize, target, None)));
+        });
+
+        // A* with great-circle bounds from the matching .co coordinate file
+        let co_path = ensure_gz_decompressed(name, &url_gz.replace(".gr.gz", ".co.gz"), "co");
+        let points = dimacs::read_coordinates(BufReader::new(File::open(co_path).expect("open .co file")))
+            .expect("parse .co file")
+            .into_iter()
+            .map(|(x, y)| (x / 1e6, y / 1e6))
+            .collect();
+        let coords = Coordinates::new(points, Metric::GreatCircle).calibrated(&bm_graph);
+        group.bench_function(BenchmarkId::new("AStar_p2p", name), |b| {
+            b.iter(|| black_box(sp.astar(1usize, target, &coords, None)));
+        });
+
+        // contraction hierarchy: preprocessing runs once, outside the timing
+        let mut ch = ContractionHierarchy::new(bm_graph.clone());
+        group.bench_function(BenchmarkId::new("CH_p2p", name), |b| {
+            b.iter(|| black_box(ch.query(1usize, target)));
+        });
+
+        // integer weights: compare against the strong integer baselines too
+        group.bench_function(BenchmarkId::new("BMSSP_radix", name), |b| {
+            b.iter(|| {
+                let mut sp = ShortestPath::new(bm_graph.clone()).with_queue(QueueKind::Radix);
+                black_box(sp.get(1usize))
+            });
+        });
+        for (label, kind) in [("Dijkstra_radix", QueueKind::Radix), ("Dijkstra_dial", QueueKind::Dial)] {
+            group.bench_function(BenchmarkId::new(label, name), |b| {
+                b.iter(|| black_box(dijkstra_with(&bm_graph, 1usize, kind)));
+            });
+        }
     }
 
     group.finish();
@@ -9,7 +9,7 @@ use reqwest::blocking::Client;
 /// If missing, downloads `url_gz` and decompresses it.
 pub fn ensure_snap_txt(name: &str, url_gz: &str) -> PathBuf {
     let data_dir = Path::new("data");
-    let _ = create_dir_all(&data_dir);
+    let _ = create_dir_all(data_dir);
 
     let txt_path = data_dir.join(format!("{}.txt", name));
     if txt_path.exists() {
@@ -43,9 +43,10 @@ pub fn ensure_snap_txt(name: &str, url_gz: &str) -> PathBuf {
 
 /// Ensure a generic `.gz` is downloaded and decompressed to a target extension.
 /// Returns the decompressed file path under `data/<name>.<out_ext>`.
+#[allow(dead_code)]
 pub fn ensure_gz_decompressed(name: &str, url_gz: &str, out_ext: &str) -> PathBuf {
     let data_dir = Path::new("data");
-    let _ = create_dir_all(&data_dir);
+    let _ = create_dir_all(data_dir);
 
     let out_path = data_dir.join(format!("{}.{}", name, out_ext));
     if out_path.exists() {
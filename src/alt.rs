@@ -0,0 +1,259 @@
+use std::io::{self, Read, Write};
+
+use crate::{
+    ShortestPath, binary,
+    heuristic::Heuristic,
+    models::{Length, Vertex},
+};
+
+/// How [`Landmarks::select`] places landmarks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    /// Each landmark is the vertex farthest from the landmarks chosen so far.
+    #[default]
+    Farthest,
+    /// Goldberg and Werneck's avoid heuristic: grow a shortest-path tree from
+    /// the farthest vertex, weight every vertex by how much the current bounds
+    /// underestimate its distance, and descend into the heaviest subtree that
+    /// holds no landmark yet.
+    Avoid,
+}
+
+/// Landmark distance tables for ALT (A*, landmarks, triangle inequality).
+///
+/// For a landmark `L` the triangle inequality gives
+/// `d(v, t) >= d(L, t) - d(L, v)` and `d(v, t) >= d(v, L) - d(t, L)`; the
+/// largest such bound over all landmarks is a consistent A* heuristic that
+/// needs no coordinates.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Landmarks {
+    landmarks: Vec<Vertex>,
+    // from[i][v] = d(landmarks[i], v) and to[i][v] = d(v, landmarks[i])
+    from: Vec<Vec<Length>>,
+    to: Vec<Vec<Length>>,
+}
+
+impl Landmarks {
+    const MAGIC: &[u8; 8] = b"BMSSPALT";
+
+    /// Picks up to `count` landmarks and computes their distance tables with
+    /// `sp`, one [`ShortestPath::get`] and one [`ShortestPath::get_to`] each.
+    pub fn select(sp: &mut ShortestPath, count: usize, selection: Selection) -> Self {
+        let mut landmarks = Self::default();
+        let n = sp.graph().len();
+        let Some(start) = (0..n).max_by_key(|&v| sp.graph()[v].len()) else {
+            return landmarks;
+        };
+
+        // min over the chosen landmarks of d(L, v); starts out as d(start, v)
+        let mut coverage = sp.get(start);
+        while landmarks.len() < count {
+            let Some(far) = farthest(&coverage) else {
+                break;
+            };
+            let landmark = match selection {
+                Selection::Farthest => far,
+                Selection::Avoid => landmarks.avoid(sp, far),
+            };
+            if landmarks.landmarks.contains(&landmark) {
+                break;
+            }
+
+            landmarks.push(sp, landmark);
+            if landmarks.len() == 1 {
+                coverage.fill(Length::INFINITY);
+            }
+            for (c, &d) in coverage
+                .iter_mut()
+                .zip(&landmarks.from[landmarks.len() - 1])
+            {
+                *c = c.min(d);
+            }
+        }
+        landmarks
+    }
+
+    pub fn landmarks(&self) -> &[Vertex] {
+        &self.landmarks
+    }
+
+    pub fn len(&self) -> usize {
+        self.landmarks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.landmarks.is_empty()
+    }
+
+    pub fn write(&self, mut w: impl Write) -> io::Result<()> {
+        binary::write_magic(&mut w, Self::MAGIC)?;
+        binary::write_usizes(&mut w, &self.landmarks)?;
+        for (from, to) in self.from.iter().zip(&self.to) {
+            binary::write_f32s(&mut w, from)?;
+            binary::write_f32s(&mut w, to)?;
+        }
+        Ok(())
+    }
+
+    pub fn read(mut r: impl Read) -> io::Result<Self> {
+        binary::read_magic(&mut r, Self::MAGIC)?;
+        let mut landmarks = Self {
+            landmarks: binary::read_usizes(&mut r)?,
+            ..Default::default()
+        };
+        for _ in 0..landmarks.len() {
+            landmarks.from.push(binary::read_f32s(&mut r)?);
+            landmarks.to.push(binary::read_f32s(&mut r)?);
+        }
+
+        // every table covers the same vertices, the landmarks among them
+        let n = landmarks.from.first().map_or(0, Vec::len);
+        if landmarks
+            .from
+            .iter()
+            .chain(&landmarks.to)
+            .any(|table| table.len() != n)
+            || landmarks.landmarks.iter().any(|&v| v >= n)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "corrupt landmark tables",
+            ));
+        }
+        Ok(landmarks)
+    }
+
+    fn push(&mut self, sp: &mut ShortestPath, landmark: Vertex) {
+        self.landmarks.push(landmark);
+        self.from.push(sp.get(landmark));
+        self.to.push(sp.get_to(landmark, None));
+    }
+
+    /// Leaf reached by descending from `root` into the heaviest landmark-free
+    /// subtree of its shortest-path tree.
+    fn avoid(&self, sp: &mut ShortestPath, root: Vertex) -> Vertex {
+        let dist = sp.get(root);
+        let n = dist.len();
+
+        let mut children = vec![Vec::new(); n];
+        for (v, parent) in sp.parents().iter().enumerate() {
+            if let Some(u) = *parent {
+                children[u].push(v);
+            }
+        }
+
+        // pre-order from the root; reversed it visits children before parents
+        let mut order = vec![root];
+        let mut i = 0;
+        while let Some(&u) = order.get(i) {
+            order.extend(&children[u]);
+            i += 1;
+        }
+
+        let mut size = vec![0.0; n];
+        let mut has_landmark = vec![false; n];
+        for &v in self.landmarks.iter() {
+            has_landmark[v] = true;
+        }
+        for &v in order.iter().rev() {
+            let mut total = dist[v] - self.estimate(root, v);
+            for &c in &children[v] {
+                has_landmark[v] |= has_landmark[c];
+                total += size[c];
+            }
+            size[v] = if has_landmark[v] { 0.0 } else { total };
+        }
+
+        let mut v = root;
+        while let Some(&c) = children[v]
+            .iter()
+            .max_by(|&&a, &&b| size[a].total_cmp(&size[b]))
+        {
+            if size[c] <= 0.0 {
+                break;
+            }
+            v = c;
+        }
+        v
+    }
+}
+
+/// The vertex with the largest finite distance, if any.
+fn farthest(dist: &[Length]) -> Option<Vertex> {
+    dist.iter()
+        .enumerate()
+        .filter(|(_, d)| d.is_finite())
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(v, _)| v)
+}
+
+impl Heuristic for Landmarks {
+    fn estimate(&self, v: Vertex, target: Vertex) -> Length {
+        let mut bound: Length = 0.0;
+        for (from, to) in self.from.iter().zip(&self.to) {
+            // NOTE: a landmark that reaches v but not the target (or is reached
+            // from the target but not from v) proves the target unreachable
+            if from[v].is_finite() {
+                bound = bound.max(from[target] - from[v]);
+            }
+            if to[target].is_finite() {
+                bound = bound.max(to[v] - to[target]);
+            }
+        }
+        bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::random_grid;
+
+    #[test]
+    fn test_alt_is_admissible_and_exact() {
+        for selection in [Selection::Farthest, Selection::Avoid] {
+            let mut sp = ShortestPath::new(random_grid(20, 1));
+            let landmarks = Landmarks::select(&mut sp, 4, selection);
+            assert_eq!(landmarks.len(), 4, "{selection:?}");
+
+            let (s, t) = (21, 377);
+            let distances = sp.get_to(t, None);
+            for (v, &d) in distances.iter().enumerate() {
+                assert!(landmarks.estimate(v, t) <= d, "{selection:?} {v}");
+            }
+
+            let route = sp.astar(s, t, &landmarks, None).unwrap();
+            assert_eq!(route.length, distances[s]);
+            assert!(route.settled < 400, "{selection:?} {}", route.settled);
+        }
+    }
+
+    #[test]
+    fn test_landmarks_roundtrip() {
+        let mut sp = ShortestPath::new(random_grid(8, 2));
+        let landmarks = Landmarks::select(&mut sp, 3, Selection::Avoid);
+
+        let mut buf = Vec::new();
+        landmarks.write(&mut buf).unwrap();
+        assert_eq!(Landmarks::read(buf.as_slice()).unwrap(), landmarks);
+        assert!(Landmarks::read(&buf[1..]).is_err());
+    }
+
+    #[test]
+    fn test_mismatched_tables_are_rejected() {
+        let mut sp = ShortestPath::new(random_grid(4, 3));
+        let mut landmarks = Landmarks::select(&mut sp, 2, Selection::Farthest);
+        landmarks.to[1].pop();
+
+        let mut buf = Vec::new();
+        landmarks.write(&mut buf).unwrap();
+        let err = Landmarks::read(buf.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        landmarks.to[1].push(0.0);
+        landmarks.landmarks[0] = 16;
+        buf.clear();
+        landmarks.write(&mut buf).unwrap();
+        assert!(Landmarks::read(buf.as_slice()).is_err());
+    }
+}
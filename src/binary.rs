@@ -0,0 +1,90 @@
+//! Little-endian helpers for the on-disk formats of the preprocessing data.
+
+use std::io::{self, Read, Write};
+
+pub fn write_magic(w: &mut impl Write, magic: &[u8; 8]) -> io::Result<()> {
+    w.write_all(magic)
+}
+
+pub fn read_magic(r: &mut impl Read, magic: &[u8; 8]) -> io::Result<()> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    if &buf != magic {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unexpected file format",
+        ));
+    }
+    Ok(())
+}
+
+pub fn write_u64(w: &mut impl Write, x: u64) -> io::Result<()> {
+    w.write_all(&x.to_le_bytes())
+}
+
+pub fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub fn read_usize(r: &mut impl Read) -> io::Result<usize> {
+    usize::try_from(read_u64(r)?).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub fn write_f32(w: &mut impl Write, x: f32) -> io::Result<()> {
+    w.write_all(&x.to_le_bytes())
+}
+
+pub fn read_f32(r: &mut impl Read) -> io::Result<f32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(f32::from_le_bytes(buf))
+}
+
+/// Writes the length followed by the values.
+pub fn write_f32s(w: &mut impl Write, xs: &[f32]) -> io::Result<()> {
+    write_u64(w, xs.len() as u64)?;
+    xs.iter().try_for_each(|&x| write_f32(w, x))
+}
+
+pub fn read_f32s(r: &mut impl Read) -> io::Result<Vec<f32>> {
+    let len = read_usize(r)?;
+    (0..len).map(|_| read_f32(r)).collect()
+}
+
+/// Writes the length followed by the values.
+pub fn write_usizes(w: &mut impl Write, xs: &[usize]) -> io::Result<()> {
+    write_u64(w, xs.len() as u64)?;
+    xs.iter().try_for_each(|&x| write_u64(w, x as u64))
+}
+
+pub fn read_usizes(r: &mut impl Read) -> io::Result<Vec<usize>> {
+    let len = read_usize(r)?;
+    (0..len).map(|_| read_usize(r)).collect()
+}
+
+/// LEB128: seven bits per byte, high bit set on all but the last byte.
+pub fn write_varint(w: &mut impl Write, mut x: u64) -> io::Result<()> {
+    while x >= 0x80 {
+        w.write_all(&[x as u8 | 0x80])?;
+        x >>= 7;
+    }
+    w.write_all(&[x as u8])
+}
+
+pub fn read_varint(r: &mut impl Read) -> io::Result<u64> {
+    let mut x = 0;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        x |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] < 0x80 {
+            return Ok(x);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "varint too long",
+    ))
+}
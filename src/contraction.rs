@@ -0,0 +1,511 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    io::{self, Read, Write},
+};
+
+use crate::{
+    binary,
+    filter::Filter,
+    models::{Edge, Graph, Length, Vertex},
+    profiles::Lengths,
+    shortest_path::{Route, search::Search},
+};
+
+/// Vertices a witness search may settle before it gives up and lets the
+/// shortcut through.
+const WITNESS_LIMIT: usize = 500;
+
+/// Edge of the overlay graph while contracting; `middle` is the contracted
+/// vertex a shortcut bypasses.
+#[derive(Debug, Clone, Copy)]
+struct Arc {
+    vertex: Vertex,
+    length: Length,
+    middle: Option<Vertex>,
+}
+
+/// Contraction hierarchy for fast repeated point-to-point queries.
+///
+/// Vertices are contracted one by one in order of edge difference (shortcuts
+/// added minus edges removed, plus the number of already contracted
+/// neighbours). Contracting `v` adds a shortcut `u -> w` for every path
+/// `u -> v -> w` unless a witness search finds a path at least as short
+/// avoiding `v`. A query then only follows edges towards higher-ranked
+/// vertices, forward from `s` and backward from `t`.
+///
+/// The augmented graph can be saved with [`Self::write`] so preprocessing
+/// runs once.
+#[derive(Debug, Default)]
+pub struct ContractionHierarchy {
+    rank: Vec<usize>,
+    // up[u] holds u -> v with rank[u] < rank[v]; down[v] holds u -> v with
+    // rank[u] > rank[v], stored as v -> u for the backward search
+    up: Graph,
+    down: Graph,
+    // contracted vertex bypassed by the edge at the same position of up/down
+    up_middle: Vec<Vec<Option<Vertex>>>,
+    down_middle: Vec<Vec<Option<Vertex>>>,
+    searches: [Search; 2],
+}
+
+impl ContractionHierarchy {
+    const MAGIC: &[u8; 8] = b"BMSSP_CH";
+
+    /// Contracts every vertex.
+    pub fn new(graph: impl Into<Graph>) -> Self {
+        let graph = graph.into();
+        Contraction::new(&graph).run()
+    }
+
+    /// Position of `v` in the contraction order.
+    pub fn rank(&self, v: Vertex) -> usize {
+        self.rank[v]
+    }
+
+    /// Number of shortcut edges added by the preprocessing.
+    pub fn shortcuts(&self) -> usize {
+        self.up_middle
+            .iter()
+            .chain(&self.down_middle)
+            .flatten()
+            .filter(|middle| middle.is_some())
+            .count()
+    }
+
+    /// Shortest route from `s` to `t` with shortcuts unpacked into original
+    /// edges; `settled` counts the vertices settled by both upward searches.
+    ///
+    /// Returns `None` if `t` is unreachable from `s`.
+    pub fn query(&mut self, s: Vertex, t: Vertex) -> Option<Route> {
+        let n = self.rank.len();
+        let [forward, backward] = &mut self.searches;
+        forward.reset(n, s);
+        forward.heap.push(s, 0.0);
+        backward.reset(n, t);
+        backward.heap.push(t, 0.0);
+
+        let mut best = if s == t {
+            (0.0, Some(s))
+        } else {
+            (Length::INFINITY, None)
+        };
+
+        // NOTE: unlike plain bidirectional Dijkstra, each side must run until
+        // its own frontier passes the best length, since the upward searches
+        // do not settle vertices in global distance order
+        let mut settled = 0;
+        loop {
+            let (top_f, top_b) = (forward.top(), backward.top());
+            if top_f.min(top_b) >= best.0 {
+                break;
+            }
+            settled += 1;
+            if top_f <= top_b {
+                forward.step(
+                    &self.up,
+                    &Filter::default(),
+                    Lengths::Graph,
+                    backward,
+                    &[],
+                    &mut best,
+                );
+            } else {
+                backward.step(
+                    &self.down,
+                    &Filter::default(),
+                    Lengths::Graph,
+                    forward,
+                    &[],
+                    &mut best,
+                );
+            }
+        }
+
+        let (length, vertex) = (best.0, best.1?);
+        let mut chain = forward.path(vertex);
+        chain.reverse();
+        chain.extend(backward.path(vertex).into_iter().skip(1));
+
+        Some(Route {
+            length,
+            path: self.unpack(&chain),
+            settled,
+        })
+    }
+
+    pub fn write(&self, mut w: impl Write) -> io::Result<()> {
+        binary::write_magic(&mut w, Self::MAGIC)?;
+        binary::write_usizes(&mut w, &self.rank)?;
+        for (graph, middles) in [(&self.up, &self.up_middle), (&self.down, &self.down_middle)] {
+            for (edges, middles) in graph.iter().zip(middles) {
+                let vertices: Vec<_> = edges.iter().map(|edge| *edge.vertex()).collect();
+                let lengths: Vec<_> = edges.iter().map(|edge| *edge.length()).collect();
+                let middles: Vec<_> = middles.iter().map(|m| m.unwrap_or(usize::MAX)).collect();
+                binary::write_usizes(&mut w, &vertices)?;
+                binary::write_f32s(&mut w, &lengths)?;
+                binary::write_usizes(&mut w, &middles)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads a hierarchy saved by [`Self::write`].
+    ///
+    /// Fails with `InvalidData` unless the ranks are a permutation, every edge
+    /// leads to a higher-ranked vertex and every shortcut bypasses a vertex
+    /// ranked below both ends, which is what makes unpacking terminate.
+    pub fn read(mut r: impl Read) -> io::Result<Self> {
+        let corrupt =
+            || io::Error::new(io::ErrorKind::InvalidData, "corrupt contraction hierarchy");
+
+        binary::read_magic(&mut r, Self::MAGIC)?;
+        let rank = binary::read_usizes(&mut r)?;
+        let n = rank.len();
+        let mut seen = vec![false; n];
+        for &i in &rank {
+            if i >= n || std::mem::replace(&mut seen[i], true) {
+                return Err(corrupt());
+            }
+        }
+
+        // both directions store u -> v with rank[u] < rank[v]
+        let mut read_graph = || -> io::Result<(Graph, Vec<Vec<Option<Vertex>>>)> {
+            let (mut graph, mut middles) = (Vec::with_capacity(n), Vec::with_capacity(n));
+            for u in 0..n {
+                let vertices = binary::read_usizes(&mut r)?;
+                let lengths = binary::read_f32s(&mut r)?;
+                let middle = binary::read_usizes(&mut r)?;
+                if lengths.len() != vertices.len()
+                    || middle.len() != vertices.len()
+                    || vertices.iter().zip(&middle).any(|(&v, &m)| {
+                        v >= n
+                            || rank[v] <= rank[u]
+                            || (m != usize::MAX && (m >= n || rank[m] >= rank[u]))
+                    })
+                {
+                    return Err(corrupt());
+                }
+                graph.push(
+                    vertices
+                        .into_iter()
+                        .zip(lengths)
+                        .map(|(v, l)| Edge::new(v, l))
+                        .collect(),
+                );
+                middles.push(
+                    middle
+                        .into_iter()
+                        .map(|m| (m != usize::MAX).then_some(m))
+                        .collect(),
+                );
+            }
+            Ok((Graph::new(graph).map_err(|_| corrupt())?, middles))
+        };
+        let (up, up_middle) = read_graph()?;
+        let (down, down_middle) = read_graph()?;
+
+        Ok(Self {
+            rank,
+            up,
+            down,
+            up_middle,
+            down_middle,
+            ..Default::default()
+        })
+    }
+
+    /// Replaces every shortcut on `chain` by the two edges it bypasses.
+    fn unpack(&self, chain: &[Vertex]) -> Vec<Vertex> {
+        let mut path = chain[..1].to_vec();
+        let mut stack = Vec::new();
+        for pair in chain.windows(2) {
+            stack.push((pair[0], pair[1]));
+            while let Some((a, b)) = stack.pop() {
+                match self.middle(a, b) {
+                    Some(m) => {
+                        stack.push((m, b));
+                        stack.push((a, m));
+                    }
+                    None => path.push(b),
+                }
+            }
+        }
+        path
+    }
+
+    fn middle(&self, a: Vertex, b: Vertex) -> Option<Vertex> {
+        let (edges, middles, to) = if self.rank[a] < self.rank[b] {
+            (&self.up[a], &self.up_middle[a], b)
+        } else {
+            (&self.down[b], &self.down_middle[b], a)
+        };
+        let i = edges.iter().position(|edge| *edge.vertex() == to)?;
+        middles[i]
+    }
+}
+
+/// Preprocessing state: the overlay graph of not yet contracted vertices.
+struct Contraction {
+    out: Vec<Vec<Arc>>,
+    inc: Vec<Vec<Arc>>,
+    contracted_neighbours: Vec<usize>,
+    witness: Search,
+    rank: Vec<usize>,
+    up: Vec<Vec<Edge>>,
+    down: Vec<Vec<Edge>>,
+    up_middle: Vec<Vec<Option<Vertex>>>,
+    down_middle: Vec<Vec<Option<Vertex>>>,
+}
+
+impl Contraction {
+    fn new(graph: &Graph) -> Self {
+        let n = graph.len();
+        let mut this = Self {
+            out: vec![Vec::new(); n],
+            inc: vec![Vec::new(); n],
+            contracted_neighbours: vec![0; n],
+            witness: Search::default(),
+            rank: vec![0; n],
+            up: vec![Vec::new(); n],
+            down: vec![Vec::new(); n],
+            up_middle: vec![Vec::new(); n],
+            down_middle: vec![Vec::new(); n],
+        };
+        for (u, edges) in graph.iter().enumerate() {
+            for edge in edges {
+                // NOTE: self-loops never lie on a shortest path
+                if *edge.vertex() != u {
+                    this.add_arc(u, *edge.vertex(), *edge.length(), None);
+                }
+            }
+        }
+        this
+    }
+
+    fn run(mut self) -> ContractionHierarchy {
+        let n = self.out.len();
+        let mut queue: BinaryHeap<_> = (0..n).map(|v| Reverse((self.priority(v), v))).collect();
+
+        let mut next_rank = 0;
+        while let Some(Reverse((_, v))) = queue.pop() {
+            // NOTE: priorities go stale as neighbours are contracted; recompute
+            // lazily and requeue if `v` is no longer the minimum
+            let priority = self.priority(v);
+            if let Some(&Reverse((top, _))) = queue.peek()
+                && priority > top
+            {
+                queue.push(Reverse((priority, v)));
+                continue;
+            }
+            self.contract(v, next_rank);
+            next_rank += 1;
+        }
+        ContractionHierarchy {
+            rank: self.rank,
+            up: Graph::from(self.up),
+            down: Graph::from(self.down),
+            up_middle: self.up_middle,
+            down_middle: self.down_middle,
+            ..Default::default()
+        }
+    }
+
+    fn priority(&mut self, v: Vertex) -> i64 {
+        let shortcuts = self.shortcuts(v).len() as i64;
+        let removed = (self.out[v].len() + self.inc[v].len()) as i64;
+        shortcuts - removed + self.contracted_neighbours[v] as i64
+    }
+
+    fn contract(&mut self, v: Vertex, rank: usize) {
+        let shortcuts = self.shortcuts(v);
+
+        let out = std::mem::take(&mut self.out[v]);
+        let inc = std::mem::take(&mut self.inc[v]);
+        for arc in &out {
+            self.inc[arc.vertex].retain(|a| a.vertex != v);
+        }
+        for arc in &inc {
+            self.out[arc.vertex].retain(|a| a.vertex != v);
+        }
+        // NOTE: a neighbour joined by arcs in both directions counts once
+        let mut neighbours: Vec<_> = out.iter().chain(&inc).map(|a| a.vertex).collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        for u in neighbours {
+            self.contracted_neighbours[u] += 1;
+        }
+        for (u, w, length) in shortcuts {
+            self.add_arc(u, w, length, Some(v));
+        }
+
+        self.rank[v] = rank;
+        self.up[v] = out.iter().map(|a| Edge::new(a.vertex, a.length)).collect();
+        self.up_middle[v] = out.iter().map(|a| a.middle).collect();
+        self.down[v] = inc.iter().map(|a| Edge::new(a.vertex, a.length)).collect();
+        self.down_middle[v] = inc.iter().map(|a| a.middle).collect();
+    }
+
+    /// Shortcuts `(u, w, length)` needed to contract `v`.
+    fn shortcuts(&mut self, v: Vertex) -> Vec<(Vertex, Vertex, Length)> {
+        let n = self.out.len();
+        let mut shortcuts = Vec::new();
+        for &Arc {
+            vertex: u,
+            length: uv,
+            ..
+        } in &self.inc[v]
+        {
+            let limit = self.out[v]
+                .iter()
+                .filter(|arc| arc.vertex != u)
+                .map(|arc| uv + arc.length)
+                .fold(Length::NEG_INFINITY, Length::max);
+            if limit == Length::NEG_INFINITY {
+                continue;
+            }
+
+            // Dijkstra from `u` avoiding `v`, up to the longest path through `v`
+            let witness = &mut self.witness;
+            witness.reset(n, u);
+            witness.heap.push(u, 0.0);
+            let mut settled = 0;
+            while let Some(edge) = witness.heap.pop() {
+                let (x, dist) = (*edge.vertex(), *edge.length());
+                settled += 1;
+                if dist > limit || settled > WITNESS_LIMIT {
+                    break;
+                }
+                for arc in &self.out[x] {
+                    if arc.vertex != v && witness.relax(x, arc.vertex, dist + arc.length) {
+                        witness.heap.push(arc.vertex, dist + arc.length);
+                    }
+                }
+            }
+
+            for arc in &self.out[v] {
+                let via = uv + arc.length;
+                if arc.vertex != u && witness.dist[arc.vertex] > via {
+                    shortcuts.push((u, arc.vertex, via));
+                }
+            }
+        }
+        shortcuts
+    }
+
+    /// Adds `u -> w`, keeping only the shorter of parallel edges.
+    fn add_arc(&mut self, u: Vertex, w: Vertex, length: Length, middle: Option<Vertex>) {
+        let arc = Arc {
+            vertex: w,
+            length,
+            middle,
+        };
+        match self.out[u].iter().position(|a| a.vertex == w) {
+            Some(i) if self.out[u][i].length <= length => {}
+            Some(i) => {
+                self.out[u][i] = arc;
+                let j = self.inc[w].iter().position(|a| a.vertex == u).unwrap();
+                self.inc[w][j] = Arc { vertex: u, ..arc };
+            }
+            None => {
+                self.out[u].push(arc);
+                self.inc[w].push(Arc { vertex: u, ..arc });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ShortestPath,
+        fixtures::{path_length, random_grid},
+    };
+
+    #[test]
+    fn test_query_unpacks_shortcuts() {
+        let graph = random_grid(10, 1);
+        let mut ch = ContractionHierarchy::new(graph.clone());
+        assert!(ch.shortcuts() > 0);
+
+        let mut sp = ShortestPath::new(graph.clone());
+        for s in [0, 45, 99] {
+            let distances = sp.get(s);
+            for (t, &d) in distances.iter().enumerate() {
+                let route = ch.query(s, t).unwrap();
+                assert_eq!(route.length, d, "{s} -> {t}");
+                assert_eq!((route.path[0], route.path[route.path.len() - 1]), (s, t));
+                assert_eq!(path_length(&graph, &route.path), d, "{s} -> {t}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_unreachable_and_trivial_queries() {
+        let graph = vec![
+            vec![Edge::new(1, 2.0), Edge::new(0, 0.0)],
+            Vec::new(),
+            Vec::new(),
+        ];
+        let mut ch = ContractionHierarchy::new(graph);
+        assert_eq!(ch.query(1, 0), None);
+        assert_eq!(ch.query(0, 2), None);
+        assert_eq!(ch.query(2, 2).unwrap().path, vec![2]);
+        assert_eq!(ch.query(0, 1).unwrap().length, 2.0);
+    }
+
+    #[test]
+    fn test_hierarchy_roundtrip() {
+        let mut ch = ContractionHierarchy::new(random_grid(6, 2));
+        let mut buf = Vec::new();
+        ch.write(&mut buf).unwrap();
+
+        let mut loaded = ContractionHierarchy::read(buf.as_slice()).unwrap();
+        assert_eq!(loaded.shortcuts(), ch.shortcuts());
+        for t in 0..36 {
+            assert_eq!(loaded.query(3, t), ch.query(3, t));
+        }
+        assert!(ContractionHierarchy::read(&buf[..buf.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_corrupt_hierarchies_are_rejected() {
+        let reread = |ch: &ContractionHierarchy| {
+            let mut buf = Vec::new();
+            ch.write(&mut buf).unwrap();
+            ContractionHierarchy::read(buf.as_slice()).map(|_| ())
+        };
+        let ch = ContractionHierarchy::new(random_grid(4, 3));
+        assert!(reread(&ch).is_ok());
+
+        let mut duplicate_rank = ContractionHierarchy::new(random_grid(4, 3));
+        duplicate_rank.rank[1] = duplicate_rank.rank[0];
+
+        // a shortcut bypassing its own higher end would unpack forever
+        let mut high_middle = ContractionHierarchy::new(random_grid(4, 3));
+        let (u, i) = (0..16)
+            .flat_map(|u| (0..high_middle.up[u].len()).map(move |i| (u, i)))
+            .next()
+            .unwrap();
+        high_middle.up_middle[u][i] = Some(*high_middle.up[u][i].vertex());
+
+        for ch in [duplicate_rank, high_middle] {
+            let err = reread(&ch).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_contracted_neighbours_are_distinct() {
+        // 1 is joined to 0 in both directions, 2 by a single edge
+        let graph = Graph::from(vec![
+            vec![Edge::new(1, 1.0), Edge::new(2, 1.0)],
+            vec![Edge::new(0, 1.0)],
+            Vec::new(),
+        ]);
+        let mut contraction = Contraction::new(&graph);
+        contraction.contract(0, 0);
+        assert_eq!(contraction.contracted_neighbours, [0, 1, 1]);
+    }
+}
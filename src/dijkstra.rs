@@ -0,0 +1,39 @@
+use crate::{
+    heaps::{Queue, QueueKind},
+    models::{Graph, Length, Vertex},
+};
+
+/// Plain Dijkstra from `s` using the given priority queue.
+///
+/// Meant as a baseline next to [`crate::ShortestPath`]; with
+/// [`QueueKind::Radix`] or [`QueueKind::Dial`] it is the usual choice for
+/// graphs with integer weights such as the DIMACS road networks.
+///
+/// # Panics
+/// If an integer queue is requested for a graph whose weights are not all
+/// non-negative integers.
+pub fn dijkstra(graph: &Graph, s: Vertex, kind: QueueKind) -> Vec<Length> {
+    assert!(
+        kind == QueueKind::DAry || graph.has_integer_weights(),
+        "{kind:?} queue requires non-negative integer weights"
+    );
+
+    let mut dist = vec![Length::INFINITY; graph.len()];
+    let mut queue = Queue::new(kind, graph.len(), graph.max_weight());
+    dist[s] = 0.0;
+    queue.push(s, 0.0);
+
+    while let Some(edge) = queue.pop() {
+        let u = *edge.vertex();
+        for graph_edge in &graph[u] {
+            let v = *graph_edge.vertex();
+            let new_dist = dist[u] + graph_edge.length();
+            if new_dist < dist[v] {
+                dist[v] = new_dist;
+                queue.push(v, new_dist);
+            }
+        }
+    }
+
+    dist
+}
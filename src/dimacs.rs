@@ -0,0 +1,76 @@
+//! Readers for the 9th DIMACS Implementation Challenge formats.
+
+use std::io::{self, BufRead};
+
+use crate::heuristic::Point;
+
+/// Reads a `.co` coordinate file:
+///
+/// ```text
+/// c comment
+/// p aux sp co <n>
+/// v <id> <x> <y>
+/// ```
+///
+/// Ids are 1-based, so the result has `n + 1` entries with index 0 unused,
+/// matching graphs loaded with the same 1-based ids. The files store longitude
+/// and latitude in millionths of a degree; they are converted to degrees, as
+/// [`crate::Metric::GreatCircle`] expects.
+pub fn read_coordinates(reader: impl BufRead) -> io::Result<Vec<Point>> {
+    const MICRODEGREES: f64 = 1e6;
+
+    let invalid =
+        |line: &str| io::Error::new(io::ErrorKind::InvalidData, format!("bad line: {line}"));
+
+    let mut points = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let mut parts = line.split_whitespace();
+        match parts.next() {
+            Some("p") => {
+                let n: usize = parts
+                    .nth(3)
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(|| invalid(&line))?;
+                points = vec![(0.0, 0.0); n + 1];
+            }
+            Some("v") => {
+                let id: usize = parts
+                    .next()
+                    .and_then(|id| id.parse().ok())
+                    .ok_or_else(|| invalid(&line))?;
+                let mut field = || {
+                    parts
+                        .next()
+                        .and_then(|x| x.parse::<f64>().ok())
+                        .ok_or_else(|| invalid(&line))
+                };
+                let (x, y) = (field()?, field()?);
+                if id >= points.len() {
+                    points.resize(id + 1, (0.0, 0.0));
+                }
+                points[id] = (x / MICRODEGREES, y / MICRODEGREES);
+            }
+            _ => {}
+        }
+    }
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_coordinates() {
+        let input = "c sample\np aux sp co 3\nv 1 -73530767 41085396\nv 2 -73530538 41086098\n\nv 3 -73519366 41048796\n";
+        let points = read_coordinates(input.as_bytes()).unwrap();
+        assert_eq!(points.len(), 4);
+        assert_eq!(points[2], (-73.530538, 41.086098));
+
+        for line in ["v 1 x 2\n", "v 1.5 3 4\n", "v -3 3 4\n"] {
+            let err = read_coordinates(line.as_bytes()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{line}");
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{
+    ShortestPath,
+    heaps::IndexedHeap,
+    models::{Graph, Length, Vertex, Weight},
+};
+
+/// Single-source distances that follow edge updates.
+///
+/// After an update only the part of the shortest-path tree it affects is
+/// repaired, in the style of Ramalingam and Reps:
+///
+/// - a shorter or new edge `u -> v` lowers `v` and runs Dijkstra from there,
+///   touching only vertices whose distance improves;
+/// - a longer or deleted tree edge `u -> v` invalidates the subtree below
+///   `v`; each of its vertices restarts from its best predecessor outside
+///   the subtree and Dijkstra settles the subtree again.
+///
+/// Changes to edges off the tree that do not shorten anything cost `O(1)`.
+#[derive(Debug)]
+pub struct DynamicShortestPath {
+    graph: Graph,
+    // predecessors[v] lists (u, length) once per u -> v edge
+    predecessors: Vec<Vec<(Vertex, Length)>>,
+    dist: Vec<Length>,
+    parent: Vec<Option<Vertex>>,
+    heap: IndexedHeap,
+    affected: Vec<bool>,
+    repaired: usize,
+}
+
+impl DynamicShortestPath {
+    /// Computes the initial distances from `source` with [`ShortestPath`].
+    pub fn new(graph: impl Into<Graph>, source: Vertex) -> Self {
+        let graph = graph.into();
+        let mut sp = ShortestPath::new(graph);
+        let dist = sp.get(source);
+        let parent = sp.parents().to_vec();
+        let graph = sp.graph().clone();
+
+        let n = graph.len();
+        let mut predecessors = vec![Vec::new(); n];
+        for (u, edges) in graph.iter().enumerate() {
+            for edge in edges {
+                predecessors[*edge.vertex()].push((u, *edge.length()));
+            }
+        }
+        Self {
+            graph,
+            predecessors,
+            dist,
+            parent,
+            heap: IndexedHeap::new(n),
+            affected: vec![false; n],
+            repaired: 0,
+        }
+    }
+
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    pub fn distances(&self) -> &[Length] {
+        &self.dist
+    }
+
+    /// Shortest path from the source to `target`, `None` if unreachable.
+    pub fn path(&self, mut target: Vertex) -> Option<Vec<Vertex>> {
+        if !self.dist[target].is_finite() {
+            return None;
+        }
+        let mut path = vec![target];
+        while let Some(u) = self.parent[target] {
+            path.push(u);
+            target = u;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Number of vertices whose distance the last update had to recompute.
+    pub fn repaired(&self) -> usize {
+        self.repaired
+    }
+
+    pub fn insert_edge(&mut self, u: Vertex, v: Vertex, weight: Weight) {
+        self.graph.insert_edge(u, v, weight);
+        self.predecessors[v].push((u, weight.get()));
+        self.repaired = 0;
+        self.decrease(u, v, weight.get());
+    }
+
+    /// Changes the length of the first `u -> v` edge, returning the old
+    /// length, or `None` (and changing nothing) if there is no such edge.
+    pub fn update_weight(&mut self, u: Vertex, v: Vertex, weight: Weight) -> Option<Length> {
+        let old = self.graph.update_weight(u, v, weight)?;
+        let i = self.in_edge(u, v, old);
+        self.predecessors[v][i].1 = weight.get();
+        self.repaired = 0;
+        if weight.get() < old {
+            self.decrease(u, v, weight.get());
+        } else if weight.get() > old {
+            self.increase(u, v);
+        }
+        Some(old)
+    }
+
+    /// Removes the first `u -> v` edge, returning its length.
+    pub fn delete_edge(&mut self, u: Vertex, v: Vertex) -> Option<Length> {
+        let old = self.graph.delete_edge(u, v)?;
+        let i = self.in_edge(u, v, old);
+        self.predecessors[v].swap_remove(i);
+        self.repaired = 0;
+        self.increase(u, v);
+        Some(old)
+    }
+
+    /// Index in `predecessors[v]` of a `u -> v` edge of the given length; any
+    /// of several parallel edges with that length will do.
+    fn in_edge(&self, u: Vertex, v: Vertex, length: Length) -> usize {
+        self.predecessors[v]
+            .iter()
+            .position(|&(x, l)| x == u && l == length)
+            .expect("predecessors follow the graph")
+    }
+
+    fn decrease(&mut self, u: Vertex, v: Vertex, length: Length) {
+        let new_dist = self.dist[u] + length;
+        if new_dist < self.dist[v] {
+            self.dist[v] = new_dist;
+            self.parent[v] = Some(u);
+            self.heap.push(v, new_dist);
+            self.settle();
+        }
+    }
+
+    fn increase(&mut self, u: Vertex, v: Vertex) {
+        if self.parent[v] != Some(u) {
+            return;
+        }
+
+        // the subtree below v, found through the out-edges of tree vertices
+        let mut subtree = vec![v];
+        self.affected[v] = true;
+        let mut i = 0;
+        while let Some(&x) = subtree.get(i) {
+            for edge in &self.graph[x] {
+                let y = *edge.vertex();
+                if self.parent[y] == Some(x) && !self.affected[y] {
+                    self.affected[y] = true;
+                    subtree.push(y);
+                }
+            }
+            i += 1;
+        }
+        for &a in &subtree {
+            self.dist[a] = Length::INFINITY;
+            self.parent[a] = None;
+        }
+
+        // NOTE: restart every affected vertex from its best unaffected
+        // predecessor; the rest of the subtree is reached by `settle`
+        for &a in &subtree {
+            for &(x, length) in &self.predecessors[a] {
+                let new_dist = self.dist[x] + length;
+                if !self.affected[x] && new_dist < self.dist[a] {
+                    self.dist[a] = new_dist;
+                    self.parent[a] = Some(x);
+                }
+            }
+            if self.dist[a].is_finite() {
+                self.heap.push(a, self.dist[a]);
+            }
+        }
+        for &a in &subtree {
+            self.affected[a] = false;
+        }
+        self.settle();
+    }
+
+    /// Dijkstra from the vertices queued by an update.
+    fn settle(&mut self) {
+        while let Some(edge) = self.heap.pop() {
+            let (u, dist) = (*edge.vertex(), *edge.length());
+            self.repaired += 1;
+            for edge in &self.graph[u] {
+                let v = *edge.vertex();
+                let new_dist = dist + edge.length();
+                if new_dist < self.dist[v] {
+                    self.dist[v] = new_dist;
+                    self.parent[v] = Some(u);
+                    self.heap.push(v, new_dist);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Edge;
+
+    fn weight(length: Length) -> Weight {
+        Weight::new(length).unwrap()
+    }
+
+    #[test]
+    fn test_updates_repair_locally() {
+        // a path 0 -> 1 -> ... -> 9 with a detour 0 -> 5
+        let mut graph: Vec<Vec<Edge>> = (0..10)
+            .map(|u| {
+                if u < 9 {
+                    vec![Edge::new(u + 1, 1.0)]
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect();
+        graph[0].push(Edge::new(5, 10.0));
+        let mut dsp = DynamicShortestPath::new(graph, 0);
+        assert_eq!(dsp.distances()[9], 9.0);
+
+        // raising an edge off the tree changes nothing
+        assert_eq!(dsp.update_weight(0, 5, weight(20.0)), Some(10.0));
+        assert_eq!(dsp.repaired(), 0);
+
+        // a shortcut only lowers the tail of the path
+        dsp.insert_edge(0, 7, weight(2.0));
+        assert_eq!(dsp.distances()[9], 4.0);
+        assert_eq!(dsp.repaired(), 3);
+        assert_eq!(dsp.path(9), Some(vec![0, 7, 8, 9]));
+
+        // cutting the path sends 3..=6 through the detour, 7.. keep the shortcut
+        assert_eq!(dsp.delete_edge(2, 3), Some(1.0));
+        assert_eq!(dsp.distances()[3], Length::INFINITY);
+        assert_eq!(dsp.distances()[6], 21.0);
+        assert_eq!(dsp.distances()[9], 4.0);
+        assert_eq!(dsp.delete_edge(2, 3), None);
+    }
+
+    #[test]
+    fn test_parallel_edges_keep_their_lengths() {
+        // two 0 -> 1 edges; updating the first must not touch the second
+        let graph = vec![vec![Edge::new(1, 4.0), Edge::new(1, 6.0)], Vec::new()];
+        let mut dsp = DynamicShortestPath::new(graph, 0);
+        assert_eq!(dsp.update_weight(0, 1, weight(9.0)), Some(4.0));
+        assert_eq!(dsp.distances(), [0.0, 6.0]);
+        assert_eq!(dsp.delete_edge(0, 1), Some(9.0));
+        assert_eq!(dsp.update_weight(0, 1, weight(7.0)), Some(6.0));
+        assert_eq!(dsp.distances(), [0.0, 7.0]);
+    }
+
+    #[test]
+    fn test_zero_weight_cycle_subtree() {
+        let graph = vec![
+            vec![Edge::new(1, 1.0), Edge::new(2, 5.0)],
+            vec![Edge::new(2, 0.0)],
+            vec![Edge::new(1, 0.0)],
+        ];
+        let mut dsp = DynamicShortestPath::new(graph, 0);
+        assert_eq!(dsp.distances(), [0.0, 1.0, 1.0]);
+        dsp.delete_edge(0, 1);
+        assert_eq!(dsp.distances(), [0.0, 5.0, 5.0]);
+        assert_eq!(dsp.path(1), Some(vec![0, 2, 1]));
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt;
+
+use crate::models::{Edge, Length, Vertex};
+
+/// Why a length cannot be used as an edge weight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeightError {
+    NaN,
+    Infinite,
+    Negative(Length),
+}
+
+impl fmt::Display for WeightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NaN => write!(f, "weight is NaN"),
+            Self::Infinite => write!(f, "weight is infinite"),
+            Self::Negative(length) => write!(f, "weight {length} is negative"),
+        }
+    }
+}
+
+impl std::error::Error for WeightError {}
+
+/// An edge of the graph carries an invalid weight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GraphError {
+    pub from: Vertex,
+    pub to: Vertex,
+    pub error: WeightError,
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "edge {} -> {}: {}", self.from, self.to, self.error)
+    }
+}
+
+impl std::error::Error for GraphError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// A cycle of negative total length, so shortest paths are undefined.
+///
+/// `edges[i]` leads from `vertices[i]` to the next vertex of the cycle; the
+/// last edge closes it back to `vertices[0]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NegativeCycle {
+    pub vertices: Vec<Vertex>,
+    pub edges: Vec<Edge>,
+    pub length: Length,
+}
+
+impl NegativeCycle {
+    pub(crate) fn new(vertices: Vec<Vertex>, edges: Vec<Edge>) -> Self {
+        let length = edges.iter().map(|edge| *edge.length() as f64).sum::<f64>() as Length;
+        Self {
+            vertices,
+            edges,
+            length,
+        }
+    }
+}
+
+impl fmt::Display for NegativeCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "negative cycle of length {} through", self.length)?;
+        for v in &self.vertices {
+            write!(f, " {v}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NegativeCycle {}
@@ -0,0 +1,73 @@
+use std::fmt;
+
+use crate::{
+    models::{Edge, Vertex},
+    sets::BitSet,
+};
+
+/// Decides whether the edge `(u, edge)` may be used.
+pub type EdgePredicate<'a> = &'a dyn Fn(Vertex, &Edge) -> bool;
+
+/// Query-time restrictions on the graph, such as road closures.
+///
+/// Blocked vertices are never entered and edges rejected by the predicate are
+/// never relaxed; the graph itself is left untouched. The default filter lets
+/// everything through.
+#[derive(Default, Clone, Copy)]
+pub struct Filter<'a> {
+    blocked: Option<&'a BitSet>,
+    edges: Option<EdgePredicate<'a>>,
+    // whether the search runs on the transposed graph, where the edge
+    // `(u, v)` the predicate expects is stored as `v -> u`
+    transposed: bool,
+}
+
+impl<'a> Filter<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_blocked(mut self, blocked: &'a BitSet) -> Self {
+        self.blocked = Some(blocked);
+        self
+    }
+
+    /// Keeps only the edges `(u, edge)` for which `predicate` returns `true`.
+    pub fn with_edges(mut self, predicate: EdgePredicate<'a>) -> Self {
+        self.edges = Some(predicate);
+        self
+    }
+
+    /// The same filter for a search over the transposed graph.
+    pub(crate) fn transposed(mut self) -> Self {
+        self.transposed = !self.transposed;
+        self
+    }
+
+    pub fn blocks(&self, v: Vertex) -> bool {
+        self.blocked.is_some_and(|blocked| blocked.contains(v))
+    }
+
+    /// Whether the edge from `u` may be relaxed.
+    #[inline]
+    pub fn allows(&self, u: Vertex, edge: &Edge) -> bool {
+        !self.blocks(*edge.vertex())
+            && self.edges.is_none_or(|predicate| {
+                if self.transposed {
+                    predicate(*edge.vertex(), &Edge::new(u, *edge.length()))
+                } else {
+                    predicate(u, edge)
+                }
+            })
+    }
+}
+
+impl fmt::Debug for Filter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Filter")
+            .field("blocked", &self.blocked)
+            .field("edges", &self.edges.map(|_| ".."))
+            .field("transposed", &self.transposed)
+            .finish()
+    }
+}
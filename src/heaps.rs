@@ -1,5 +1,5 @@
 use crate::models::{Edge, Length, Vertex};
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 #[derive(Debug, Default)]
 pub struct Entry {
@@ -19,38 +19,351 @@ impl Entry {
     }
 }
 
+/// Array-backed d-ary min-heap keyed by vertex.
+///
+/// `pos` maps every vertex to its slot in `que`, so decrease-key is a sift-up
+/// instead of a remove/insert pair and no hashing is involved.
 #[derive(Debug, Default)]
-pub struct Heap {
-    que: BTreeSet<Edge>,
-    d: HashMap<Vertex, Length>,
+pub struct IndexedHeap {
+    que: Vec<Edge>,
+    pos: Vec<usize>,
 }
 
-impl Heap {
-    pub fn new() -> Self {
+impl IndexedHeap {
+    const ARITY: usize = 4;
+    const ABSENT: usize = usize::MAX;
+
+    pub fn new(n: usize) -> Self {
         Self {
-            que: BTreeSet::new(),
-            d: HashMap::new(),
+            que: Vec::new(),
+            pos: vec![Self::ABSENT; n],
         }
     }
 
-    //TODO: we probably want to use edges always
+    /// Inserts `v` with key `l`, or decreases its key if `l` is smaller.
     pub fn push(&mut self, v: Vertex, l: Length) {
-        if let Some(&existing_dist) = self.d.get(&v) {
-            if existing_dist < l {
-                return;
-            } else if existing_dist >= l {
-                self.que.remove(&Edge::new(v, existing_dist));
+        match self.pos[v] {
+            Self::ABSENT => {
+                self.que.push(Edge::new(v, l));
+                self.pos[v] = self.que.len() - 1;
+                self.sift_up(self.que.len() - 1);
             }
+            i if l < *self.que[i].length() => {
+                self.que[i] = Edge::new(v, l);
+                self.sift_up(i);
+            }
+            _ => {}
         }
-        self.que.insert(Edge::new(v, l));
-        self.d.insert(v, l);
     }
 
     pub fn pop(&mut self) -> Option<Edge> {
-        self.que.pop_first().map(|edge| {
-            self.d.remove(edge.vertex());
-            edge
-        })
+        if self.que.is_empty() {
+            return None;
+        }
+        let edge = self.que.swap_remove(0);
+        self.pos[*edge.vertex()] = Self::ABSENT;
+        if !self.que.is_empty() {
+            self.pos[*self.que[0].vertex()] = 0;
+            self.sift_down(0);
+        }
+        Some(edge)
+    }
+
+    pub fn peek(&self) -> Option<&Edge> {
+        self.que.first()
+    }
+
+    /// Empties the heap in O(len) so it can be reused without reallocating `pos`.
+    pub fn clear(&mut self) {
+        for edge in self.que.drain(..) {
+            self.pos[*edge.vertex()] = Self::ABSENT;
+        }
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let parent = (i - 1) / Self::ARITY;
+            if self.que[i].length() >= self.que[parent].length() {
+                break;
+            }
+            self.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            let first = i * Self::ARITY + 1;
+            let last = (first + Self::ARITY).min(self.que.len());
+            let Some(child) =
+                (first..last).min_by(|&a, &b| self.que[a].length().total_cmp(self.que[b].length()))
+            else {
+                break;
+            };
+            if self.que[child].length() >= self.que[i].length() {
+                break;
+            }
+            self.swap(i, child);
+            i = child;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.que.swap(a, b);
+        self.pos[*self.que[a].vertex()] = a;
+        self.pos[*self.que[b].vertex()] = b;
+    }
+}
+
+/// Monotone radix heap over integer keys.
+///
+/// Only valid when every pushed key is at least the last popped one, which is
+/// the case for Dijkstra-style searches with non-negative integer weights.
+/// Superseded entries are left in their bucket and skipped on pop.
+#[derive(Debug, Default)]
+pub struct RadixHeap {
+    buckets: Vec<Vec<(u64, Vertex)>>,
+    last: u64,
+    key: Vec<u64>,
+}
+
+impl RadixHeap {
+    const ABSENT: u64 = u64::MAX;
+
+    pub fn new(n: usize) -> Self {
+        Self {
+            buckets: vec![Vec::new(); u64::BITS as usize + 1],
+            last: 0,
+            key: vec![Self::ABSENT; n],
+        }
+    }
+
+    pub fn push(&mut self, v: Vertex, l: Length) {
+        let k = l as u64;
+        if k >= self.key[v] {
+            return;
+        }
+        debug_assert!(k >= self.last, "radix heap keys must be monotone");
+        self.key[v] = k;
+        let i = self.bucket(k);
+        self.buckets[i].push((k, v));
+    }
+
+    pub fn pop(&mut self) -> Option<Edge> {
+        loop {
+            if self.buckets[0].is_empty() {
+                let i = self.buckets.iter().position(|bucket| !bucket.is_empty())?;
+                let items = std::mem::take(&mut self.buckets[i]);
+                self.last = items.iter().map(|&(k, _)| k).min().unwrap_or(self.last);
+                for (k, v) in items {
+                    if self.key[v] == k {
+                        let j = self.bucket(k);
+                        self.buckets[j].push((k, v));
+                    }
+                }
+                continue;
+            }
+
+            let (k, v) = self.buckets[0].pop()?;
+            if self.key[v] == k {
+                self.key[v] = Self::ABSENT;
+                return Some(Edge::new(v, k as Length));
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            for (_, v) in bucket.drain(..) {
+                self.key[v] = Self::ABSENT;
+            }
+        }
+        self.last = 0;
+    }
+
+    fn bucket(&self, k: u64) -> usize {
+        (u64::BITS - (k ^ self.last).leading_zeros()) as usize
+    }
+}
+
+/// Dial's bucket queue over integer keys: a circular array of `max_weight + 1`
+/// buckets, enough for every key between the smallest pending key and that
+/// key plus the largest weight. A single-source search never leaves that
+/// window; the sources of a multi-source base case may, and keys beyond it
+/// wait in an ordered overflow until the window reaches them.
+#[derive(Debug, Default)]
+pub struct DialQueue {
+    // bucket k % len holds key k for every k in [base, base + len)
+    buckets: Vec<Vec<(u64, Vertex)>>,
+    base: u64,
+    // entries in `buckets`, superseded ones included
+    len: usize,
+    overflow: BTreeMap<u64, Vec<Vertex>>,
+    key: Vec<u64>,
+}
+
+impl DialQueue {
+    const ABSENT: u64 = u64::MAX;
+
+    pub fn new(n: usize, max_weight: Length) -> Self {
+        Self {
+            buckets: vec![Vec::new(); max_weight as usize + 1],
+            base: 0,
+            len: 0,
+            overflow: BTreeMap::new(),
+            key: vec![Self::ABSENT; n],
+        }
+    }
+
+    pub fn push(&mut self, v: Vertex, l: Length) {
+        let k = l as u64;
+        if k >= self.key[v] {
+            return;
+        }
+        self.key[v] = k;
+        if self.len == 0 && self.overflow.is_empty() {
+            self.base = k;
+        } else if k < self.base {
+            self.rebase(k);
+        }
+        self.insert(k, v);
+    }
+
+    pub fn pop(&mut self) -> Option<Edge> {
+        loop {
+            if self.len == 0 {
+                let (&k, _) = self.overflow.first_key_value()?;
+                self.base = k;
+                self.refill();
+            }
+            let i = (self.base % self.buckets.len() as u64) as usize;
+            match self.buckets[i].pop() {
+                Some((k, v)) => {
+                    self.len -= 1;
+                    if self.key[v] == k {
+                        self.key[v] = Self::ABSENT;
+                        return Some(Edge::new(v, k as Length));
+                    }
+                }
+                None => {
+                    self.base += 1;
+                    self.refill();
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for (_, v) in self.buckets.iter_mut().flat_map(|bucket| bucket.drain(..)) {
+            self.key[v] = Self::ABSENT;
+        }
+        for v in std::mem::take(&mut self.overflow).into_values().flatten() {
+            self.key[v] = Self::ABSENT;
+        }
+        self.len = 0;
+    }
+
+    fn insert(&mut self, k: u64, v: Vertex) {
+        let width = self.buckets.len() as u64;
+        if k - self.base < width {
+            self.buckets[(k % width) as usize].push((k, v));
+            self.len += 1;
+        } else {
+            self.overflow.entry(k).or_default().push(v);
+        }
+    }
+
+    /// Moves the overflow keys that entered the window into their buckets.
+    fn refill(&mut self) {
+        let end = self.base + self.buckets.len() as u64;
+        while let Some(entry) = self.overflow.first_entry()
+            && *entry.key() < end
+        {
+            let (k, vertices) = entry.remove_entry();
+            for v in vertices {
+                if self.key[v] == k {
+                    self.insert(k, v);
+                }
+            }
+        }
+    }
+
+    /// Lowers the window to start at `k`, which only happens while the sources
+    /// of a base case are pushed, before anything was popped.
+    fn rebase(&mut self, k: u64) {
+        let pending: Vec<_> = self
+            .buckets
+            .iter_mut()
+            .flat_map(|bucket| bucket.drain(..))
+            .collect();
+        self.len = 0;
+        self.base = k;
+        for (k, v) in pending {
+            if self.key[v] == k {
+                self.insert(k, v);
+            }
+        }
+    }
+}
+
+/// Priority queue used by the base case and by [`crate::dijkstra`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum QueueKind {
+    /// Indexed d-ary heap; works with any non-negative weights.
+    #[default]
+    DAry,
+    /// Monotone radix heap; requires non-negative integer weights.
+    Radix,
+    /// Dial's bucket queue; requires non-negative integer weights.
+    Dial,
+}
+
+#[derive(Debug)]
+pub enum Queue {
+    DAry(IndexedHeap),
+    Radix(RadixHeap),
+    Dial(DialQueue),
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        Self::DAry(IndexedHeap::default())
+    }
+}
+
+impl Queue {
+    /// A queue for `n` vertices whose keys grow by at most `max_weight` per
+    /// edge; only Dial's queue depends on it.
+    pub fn new(kind: QueueKind, n: usize, max_weight: Length) -> Self {
+        match kind {
+            QueueKind::DAry => Self::DAry(IndexedHeap::new(n)),
+            QueueKind::Radix => Self::Radix(RadixHeap::new(n)),
+            QueueKind::Dial => Self::Dial(DialQueue::new(n, max_weight)),
+        }
+    }
+
+    pub fn push(&mut self, v: Vertex, l: Length) {
+        match self {
+            Self::DAry(h) => h.push(v, l),
+            Self::Radix(h) => h.push(v, l),
+            Self::Dial(h) => h.push(v, l),
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Edge> {
+        match self {
+            Self::DAry(h) => h.pop(),
+            Self::Radix(h) => h.pop(),
+            Self::Dial(h) => h.pop(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match self {
+            Self::DAry(h) => h.clear(),
+            Self::Radix(h) => h.clear(),
+            Self::Dial(h) => h.clear(),
+        }
     }
 }
 
@@ -92,13 +405,16 @@ impl BlockHeap {
     pub fn pull(&mut self) -> Entry {
         let mut s = Vec::new();
 
-        for _ in 0..self.m {
-            if let Some(edge) = self.que.pop_first() {
-                self.d.remove(edge.vertex());
-                s.push(*edge.vertex());
-            } else {
+        let mut last = None;
+        while let Some(edge) = self.que.first() {
+            // NOTE: keep pulling past `m` on ties so every pulled key stays below the bound
+            if s.len() >= self.m && last != Some(*edge.length()) {
                 break;
             }
+            let edge = self.que.pop_first().unwrap();
+            self.d.remove(edge.vertex());
+            last = Some(*edge.length());
+            s.push(*edge.vertex());
         }
 
         let b = self.que.first().map_or(self.b, |edge| *edge.length());
@@ -125,4 +441,96 @@ mod tests {
         let entry = block_heap.pull();
         assert_eq!(entry.b, 15.0);
     }
+
+    #[test]
+    fn test_block_heap_pull_ties() {
+        let mut block_heap = BlockHeap::new(1, 100.0);
+        block_heap.insert(1, 5.0);
+        block_heap.insert(2, 5.0);
+        block_heap.insert(3, 7.0);
+
+        let entry = block_heap.pull();
+        assert_eq!(entry.u_set().len(), 2);
+        assert_eq!(entry.b(), 7.0);
+    }
+
+    #[test]
+    fn test_indexed_heap_decrease_key() {
+        let mut heap = IndexedHeap::new(6);
+        for (v, l) in [(0, 9.0), (1, 4.0), (2, 7.0), (3, 1.0), (4, 8.0), (5, 6.0)] {
+            heap.push(v, l);
+        }
+        heap.push(4, 2.0);
+        heap.push(1, 5.0);
+
+        let order: Vec<_> = std::iter::from_fn(|| heap.pop())
+            .map(|edge| *edge.vertex())
+            .collect();
+        assert_eq!(order, vec![3, 4, 1, 5, 2, 0]);
+    }
+
+    #[test]
+    fn test_integer_queues_pop_in_order() {
+        for kind in [QueueKind::Radix, QueueKind::Dial] {
+            let mut queue = Queue::new(kind, 6, 9.0);
+            for (v, l) in [(0, 9.0), (1, 4.0), (2, 7.0), (3, 1.0), (4, 8.0)] {
+                queue.push(v, l);
+            }
+            queue.push(4, 2.0);
+            queue.push(1, 5.0);
+
+            let mut order = Vec::new();
+            while let Some(edge) = queue.pop() {
+                order.push(*edge.vertex());
+                if *edge.vertex() == 3 {
+                    queue.push(5, 3.0);
+                }
+            }
+            assert_eq!(order, vec![3, 4, 5, 1, 2, 0], "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn test_dial_queue_spread_out_sources() {
+        // keys far beyond the largest weight of 10, pushed out of order
+        let mut queue = DialQueue::new(4, 10.0);
+        for (v, l) in [(0, 1000.0), (1, 10.0), (2, 500.0), (3, 0.0)] {
+            queue.push(v, l);
+        }
+        queue.push(2, 400.0);
+
+        let mut order = Vec::new();
+        while let Some(edge) = queue.pop() {
+            let (v, l) = (*edge.vertex(), *edge.length());
+            order.push((v, l));
+            if v == 1 {
+                queue.push(3, 15.0);
+            }
+        }
+        assert_eq!(
+            order,
+            vec![(3, 0.0), (1, 10.0), (3, 15.0), (2, 400.0), (0, 1000.0)]
+        );
+    }
+
+    #[test]
+    fn test_dial_queue_wraps_around() {
+        // every pop pushes its vertex back at the largest weight of 3
+        let mut queue = DialQueue::new(2, 3.0);
+        queue.push(0, 0.0);
+        queue.push(1, 1.0);
+        let mut popped = Vec::new();
+        for _ in 0..10 {
+            let edge = queue.pop().unwrap();
+            popped.push(*edge.length());
+            queue.push(*edge.vertex(), edge.length() + 3.0);
+        }
+        assert_eq!(
+            popped,
+            [0.0, 1.0, 3.0, 4.0, 6.0, 7.0, 9.0, 10.0, 12.0, 13.0]
+        );
+
+        queue.clear();
+        assert_eq!(queue.pop(), None);
+    }
 }
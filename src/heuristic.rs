@@ -0,0 +1,128 @@
+use crate::models::{Graph, Length, Vertex};
+
+/// Lower bound on the remaining distance, used to direct [`crate::ShortestPath::astar`].
+pub trait Heuristic {
+    /// Estimated distance from `v` to `target`; must never overestimate.
+    ///
+    /// If it is also consistent, i.e. `estimate(u, t) <= w(u, v) + estimate(v, t)`
+    /// on every edge, A* settles every vertex at most once.
+    fn estimate(&self, v: Vertex, target: Vertex) -> Length;
+}
+
+/// A planar point, or `(longitude, latitude)` in degrees for
+/// [`Metric::GreatCircle`].
+pub type Point = (f64, f64);
+
+/// How distances between vertex coordinates are measured.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    #[default]
+    Euclidean,
+    /// Haversine distance on a sphere of Earth's mean radius, in meters.
+    GreatCircle,
+}
+
+/// Geometric lower bounds from vertex coordinates.
+///
+/// Estimates are `scale * distance(v, target)`. Edge lengths are rarely in the
+/// same unit as the coordinates, so [`Coordinates::calibrated`] picks the largest
+/// scale that keeps every edge at least as long as its estimate, which makes
+/// the heuristic both admissible and consistent.
+#[derive(Debug, Default, Clone)]
+pub struct Coordinates {
+    points: Vec<Point>,
+    metric: Metric,
+    scale: f64,
+}
+
+impl Coordinates {
+    const EARTH_RADIUS: f64 = 6_371_000.0;
+
+    pub fn new(points: Vec<Point>, metric: Metric) -> Self {
+        Self {
+            points,
+            metric,
+            scale: 1.0,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the scale to `min w(u, v) / distance(u, v)` over the edges of `graph`.
+    pub fn calibrated(mut self, graph: &Graph) -> Self {
+        let mut scale = f64::INFINITY;
+        for (u, edges) in graph.iter().enumerate() {
+            for edge in edges {
+                let distance = self.distance(u, *edge.vertex());
+                if distance > 0.0 {
+                    scale = scale.min(*edge.length() as f64 / distance);
+                }
+            }
+        }
+        // NOTE: estimates are rounded to `Length`, so keep a little slack
+        self.scale = if scale.is_finite() {
+            scale * (1.0 - 1e-6)
+        } else {
+            0.0
+        };
+        self
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn distance(&self, u: Vertex, v: Vertex) -> f64 {
+        let ((x1, y1), (x2, y2)) = (self.points[u], self.points[v]);
+        match self.metric {
+            Metric::Euclidean => (x1 - x2).hypot(y1 - y2),
+            Metric::GreatCircle => {
+                let (lat1, lat2) = (y1.to_radians(), y2.to_radians());
+                let dlat = lat2 - lat1;
+                let dlon = (x2 - x1).to_radians();
+                let a = (dlat / 2.0).sin().powi(2)
+                    + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+                2.0 * Self::EARTH_RADIUS * a.sqrt().min(1.0).asin()
+            }
+        }
+    }
+}
+
+impl Heuristic for Coordinates {
+    fn estimate(&self, v: Vertex, target: Vertex) -> Length {
+        (self.scale * self.distance(v, target)) as Length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Edge;
+
+    #[test]
+    fn test_great_circle_distance() {
+        // Paris to London is about 344 km
+        let coords = Coordinates::new(
+            vec![(2.3522, 48.8566), (-0.1276, 51.5072)],
+            Metric::GreatCircle,
+        );
+        let km = coords.distance(0, 1) / 1000.0;
+        assert!((km - 344.0).abs() < 2.0, "{km}");
+    }
+
+    #[test]
+    fn test_calibrated_scale_is_admissible() {
+        let points = vec![(0.0, 0.0), (3.0, 4.0), (6.0, 8.0)];
+        let graph = Graph::from(vec![
+            vec![Edge::new(1, 10.0)],
+            vec![Edge::new(2, 5.0)],
+            Vec::new(),
+        ]);
+        let coords = Coordinates::new(points, Metric::Euclidean).calibrated(&graph);
+        assert!((coords.scale() - 1.0).abs() < 1e-5);
+        assert!(coords.estimate(0, 2) <= 15.0);
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    binary,
+    models::{Graph, Length, Vertex},
+    shortest_path::search::Search,
+};
+
+/// Label construction statistics, see [`HubLabels::stats`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelStats {
+    /// Total number of label entries, in- and out-labels together.
+    pub entries: usize,
+    pub max_label: usize,
+    /// Entries per vertex and direction.
+    pub average_label: f64,
+    /// Vertices reached by a construction search but already covered by the
+    /// labels of earlier hubs.
+    pub pruned: usize,
+    pub elapsed: Duration,
+}
+
+/// Labels of all vertices in one direction, flattened. Hubs are stored by
+/// rank as `u32` and sorted, so two labels intersect by a linear merge.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Labels {
+    offsets: Vec<usize>,
+    hubs: Vec<u32>,
+    lengths: Vec<Length>,
+}
+
+impl Labels {
+    fn from_lists(lists: Vec<Vec<(u32, Length)>>) -> Self {
+        let mut labels = Self {
+            offsets: vec![0],
+            ..Default::default()
+        };
+        for list in lists {
+            for (hub, length) in list {
+                labels.hubs.push(hub);
+                labels.lengths.push(length);
+            }
+            labels.offsets.push(labels.hubs.len());
+        }
+        labels
+    }
+
+    fn get(&self, v: Vertex) -> (&[u32], &[Length]) {
+        let range = self.offsets[v]..self.offsets[v + 1];
+        (&self.hubs[range.clone()], &self.lengths[range])
+    }
+
+    /// Hub ranks are delta-encoded as varints, lengths written raw.
+    fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        for v in 0..self.offsets.len() - 1 {
+            let (hubs, lengths) = self.get(v);
+            binary::write_varint(w, hubs.len() as u64)?;
+            let mut last = 0;
+            for (&hub, &length) in hubs.iter().zip(lengths) {
+                binary::write_varint(w, u64::from(hub - last))?;
+                binary::write_f32(w, length)?;
+                last = hub;
+            }
+        }
+        Ok(())
+    }
+
+    fn read(r: &mut impl Read, n: usize) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "corrupt hub labels");
+        let mut lists = Vec::with_capacity(n);
+        for _ in 0..n {
+            let len = binary::read_varint(r)?;
+            let mut list = Vec::new();
+            let mut hub = 0u32;
+            for _ in 0..len {
+                let delta = u32::try_from(binary::read_varint(r)?).map_err(|_| invalid())?;
+                hub = hub.checked_add(delta).ok_or_else(invalid)?;
+                if hub as usize >= n {
+                    return Err(invalid());
+                }
+                list.push((hub, binary::read_f32(r)?));
+            }
+            lists.push(list);
+        }
+        Ok(Self::from_lists(lists))
+    }
+}
+
+/// Hub labeling distance oracle built by pruned landmark labelling.
+///
+/// Every vertex `v` gets an out-label of hubs `h` with `d(v, h)` and an
+/// in-label with `d(h, v)` such that each shortest `s`-`t` path passes
+/// through a hub in both `out(s)` and `in(t)`. Hubs are processed from high
+/// to low degree; each runs a forward and a backward Dijkstra that stops
+/// expanding wherever the labels built so far already give the distance.
+///
+/// Queries only read the labels, so an oracle can be shared between threads.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HubLabels {
+    // rank of hub h is its position in the processing order; order[rank] = h
+    order: Vec<Vertex>,
+    out: Labels,
+    inc: Labels,
+    pruned: usize,
+    elapsed: Duration,
+}
+
+impl HubLabels {
+    const MAGIC: &[u8; 8] = b"BMSSPHUB";
+
+    /// Builds the labels.
+    pub fn new(graph: impl Into<Graph>) -> Self {
+        let graph = graph.into();
+        let start = Instant::now();
+
+        let n = graph.len();
+        assert!(
+            u32::try_from(n).is_ok(),
+            "hub labels support at most 2^32 vertices"
+        );
+        let reversed = graph.reversed();
+        let mut order: Vec<Vertex> = (0..n).collect();
+        order.sort_by_key(|&v| std::cmp::Reverse((graph[v].len() + 1) * (reversed[v].len() + 1)));
+
+        let mut out = vec![Vec::new(); n];
+        let mut inc = vec![Vec::new(); n];
+        let mut search = Search::default();
+        let mut root_label = vec![Length::INFINITY; n];
+        let mut pruned = 0;
+        for (rank, &root) in order.iter().enumerate() {
+            let rank = rank as u32;
+            // forward: d(root, v) becomes an in-label entry of v, and vice versa
+            pruned += prune_search(
+                &graph,
+                root,
+                rank,
+                &mut search,
+                &mut root_label,
+                &out,
+                &mut inc,
+            );
+            pruned += prune_search(
+                &reversed,
+                root,
+                rank,
+                &mut search,
+                &mut root_label,
+                &inc,
+                &mut out,
+            );
+        }
+
+        Self {
+            order,
+            out: Labels::from_lists(out),
+            inc: Labels::from_lists(inc),
+            pruned,
+            elapsed: start.elapsed(),
+        }
+    }
+
+    /// Length of a shortest path from `s` to `t`, `INFINITY` if there is none.
+    pub fn distance(&self, s: Vertex, t: Vertex) -> Length {
+        self.meet(s, t)
+            .map_or(Length::INFINITY, |(_, length)| length)
+    }
+
+    /// Hub on a shortest path from `s` to `t`, with the path length.
+    pub fn hub(&self, s: Vertex, t: Vertex) -> Option<(Vertex, Length)> {
+        self.meet(s, t)
+            .map(|(rank, length)| (self.order[rank as usize], length))
+    }
+
+    pub fn stats(&self) -> LabelStats {
+        let n = self.order.len();
+        let sizes = (0..n).flat_map(|v| [self.out.get(v).0.len(), self.inc.get(v).0.len()]);
+        let entries = self.out.hubs.len() + self.inc.hubs.len();
+        LabelStats {
+            entries,
+            max_label: sizes.max().unwrap_or(0),
+            average_label: if n == 0 {
+                0.0
+            } else {
+                entries as f64 / (2 * n) as f64
+            },
+            pruned: self.pruned,
+            elapsed: self.elapsed,
+        }
+    }
+
+    pub fn write(&self, mut w: impl Write) -> io::Result<()> {
+        binary::write_magic(&mut w, Self::MAGIC)?;
+        binary::write_usizes(&mut w, &self.order)?;
+        self.out.write(&mut w)?;
+        self.inc.write(&mut w)?;
+        binary::write_u64(&mut w, self.pruned as u64)?;
+        binary::write_u64(&mut w, self.elapsed.as_nanos() as u64)
+    }
+
+    pub fn read(mut r: impl Read) -> io::Result<Self> {
+        binary::read_magic(&mut r, Self::MAGIC)?;
+        let order = binary::read_usizes(&mut r)?;
+        let n = order.len();
+        if order.iter().any(|&v| v >= n) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "corrupt hub labels",
+            ));
+        }
+        Ok(Self {
+            order,
+            out: Labels::read(&mut r, n)?,
+            inc: Labels::read(&mut r, n)?,
+            pruned: binary::read_usize(&mut r)?,
+            elapsed: Duration::from_nanos(binary::read_u64(&mut r)?),
+        })
+    }
+
+    /// Best common hub of `out(s)` and `in(t)` as `(rank, length)`.
+    fn meet(&self, s: Vertex, t: Vertex) -> Option<(u32, Length)> {
+        let (out_hubs, out_lengths) = self.out.get(s);
+        let (in_hubs, in_lengths) = self.inc.get(t);
+        let mut best: Option<(u32, Length)> = None;
+        let (mut i, mut j) = (0, 0);
+        while i < out_hubs.len() && j < in_hubs.len() {
+            match out_hubs[i].cmp(&in_hubs[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    let length = out_lengths[i] + in_lengths[j];
+                    if best.is_none_or(|(_, b)| length < b) {
+                        best = Some((out_hubs[i], length));
+                    }
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        best
+    }
+}
+
+/// Dijkstra from `root` over `graph`, appending `(rank, dist)` to `labels[v]`
+/// for every settled `v` whose distance the existing labels do not already
+/// give; `root_labels` are the root's labels for the opposite direction.
+/// Returns the number of pruned vertices.
+fn prune_search(
+    graph: &Graph,
+    root: Vertex,
+    rank: u32,
+    search: &mut Search,
+    root_label: &mut [Length],
+    root_labels: &[Vec<(u32, Length)>],
+    labels: &mut [Vec<(u32, Length)>],
+) -> usize {
+    // NOTE: scatter the root's label once so each check is a single scan of
+    // the label of the settled vertex
+    for &(hub, length) in &root_labels[root] {
+        root_label[hub as usize] = length;
+    }
+
+    search.reset(graph.len(), root);
+    search.heap.push(root, 0.0);
+    let mut pruned = 0;
+    while let Some(edge) = search.heap.pop() {
+        let (u, dist) = (*edge.vertex(), *edge.length());
+        let covered = labels[u]
+            .iter()
+            .any(|&(hub, length)| root_label[hub as usize] + length <= dist);
+        if covered {
+            pruned += 1;
+            continue;
+        }
+        labels[u].push((rank, dist));
+        for edge in &graph[u] {
+            let (v, new_dist) = (*edge.vertex(), dist + edge.length());
+            if search.relax(u, v, new_dist) {
+                search.heap.push(v, new_dist);
+            }
+        }
+    }
+
+    for &(hub, _) in &root_labels[root] {
+        root_label[hub as usize] = Length::INFINITY;
+    }
+    pruned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ShortestPath, fixtures, models::Edge};
+
+    // strongly connected, so every pair has a hub
+    fn sample() -> Vec<Vec<Edge>> {
+        let mut graph = fixtures::sample();
+        graph[4].push(Edge::new(0, 1.0));
+        graph
+    }
+
+    #[test]
+    fn test_labels_answer_all_pairs() {
+        let graph = sample();
+        let labels = HubLabels::new(graph.clone());
+        let mut sp = ShortestPath::new(graph);
+        let all: Vec<_> = (0..6).map(|s| sp.get(s)).collect();
+        for s in 0..6 {
+            for (t, &d) in all[s].iter().enumerate() {
+                assert_eq!(labels.distance(s, t), d, "{s} -> {t}");
+                match labels.hub(s, t) {
+                    Some((hub, length)) => {
+                        assert_eq!(length, d);
+                        assert_eq!(all[s][hub] + all[hub][t], d);
+                    }
+                    None => assert_eq!(d, f32::INFINITY),
+                }
+            }
+        }
+
+        let stats = labels.stats();
+        assert!(stats.entries >= 12 && stats.max_label >= 1);
+        assert_eq!(stats.average_label, stats.entries as f64 / 12.0);
+    }
+
+    #[test]
+    fn test_labels_roundtrip() {
+        let labels = HubLabels::new(sample());
+        let mut buf = Vec::new();
+        labels.write(&mut buf).unwrap();
+        assert_eq!(HubLabels::read(buf.as_slice()).unwrap(), labels);
+        assert!(HubLabels::read(&buf[..buf.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_varint_roundtrip() {
+        let mut buf = Vec::new();
+        for x in [0, 1, 127, 128, 300, u64::MAX] {
+            binary::write_varint(&mut buf, x).unwrap();
+        }
+        let mut r = buf.as_slice();
+        for x in [0, 1, 127, 128, 300, u64::MAX] {
+            assert_eq!(binary::read_varint(&mut r).unwrap(), x);
+        }
+    }
+}
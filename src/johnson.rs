@@ -0,0 +1,136 @@
+use crate::{
+    ShortestPath,
+    error::NegativeCycle,
+    models::{Edge, Length, Vertex},
+    negative_cycle::{CycleCheck, Potentials, find_negative_cycle},
+};
+
+/// Shortest paths on graphs with negative edges but no negative cycles.
+///
+/// Potentials are computed once by [`find_negative_cycle`]; edges are then
+/// reweighted to `w + h(u) - h(v) >= 0`, each query runs BMSSP on the
+/// reweighted graph, and distances are translated back.
+#[derive(Debug)]
+pub struct Johnson {
+    sp: ShortestPath,
+    potential: Potentials,
+}
+
+impl Johnson {
+    /// # Panics
+    /// If an edge weight is NaN or infinite.
+    pub fn new(graph: &[Vec<Edge>]) -> Result<Self, NegativeCycle> {
+        let potential = match find_negative_cycle(graph) {
+            CycleCheck::Negative(cycle) => return Err(cycle),
+            CycleCheck::Feasible(potential) => potential,
+        };
+        let reweighted: Vec<Vec<Edge>> = graph
+            .iter()
+            .enumerate()
+            .map(|(u, edges)| {
+                edges
+                    .iter()
+                    .map(|edge| {
+                        let v = *edge.vertex();
+                        let w = *edge.length() as f64 + potential[u] - potential[v];
+                        // NOTE: rounding can leave tiny negatives on tight edges
+                        Edge::new(v, (w as Length).max(0.0))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(Self {
+            sp: ShortestPath::new(reweighted),
+            potential,
+        })
+    }
+
+    /// The potentials `h` used for reweighting.
+    pub fn potential(&self) -> &Potentials {
+        &self.potential
+    }
+
+    pub fn get(&mut self, s: Vertex) -> Vec<Length> {
+        let hs = self.potential[s];
+        self.sp
+            .get(s)
+            .into_iter()
+            .zip(self.potential.iter())
+            .map(|(d, &hv)| {
+                if d.is_finite() {
+                    (d as f64 - hs + hv) as Length
+                } else {
+                    d
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_johnson_negative_edges() {
+        let mut graph = vec![Vec::new(); 5];
+        graph[0].push(Edge::new(1, 4.0));
+        graph[0].push(Edge::new(2, 2.0));
+        graph[1].push(Edge::new(3, -3.0));
+        graph[2].push(Edge::new(1, -1.0));
+        graph[3].push(Edge::new(4, 2.0));
+        graph[4].push(Edge::new(2, 3.0));
+
+        let mut johnson = Johnson::new(&graph).unwrap();
+        assert_eq!(johnson.get(0), vec![0.0, 1.0, 2.0, -2.0, 0.0]);
+        assert_eq!(johnson.get(3), vec![Length::INFINITY, 4.0, 5.0, 0.0, 2.0]);
+    }
+
+    #[test]
+    fn test_johnson_matches_bellman_ford() {
+        use rand::{Rng, SeedableRng, rngs::StdRng};
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let n = 200;
+        // w = base + h(v) - h(u) with base >= 0 gives negative edges but no
+        // negative cycles
+        let h: Vec<i32> = (0..n).map(|_| rng.random_range(0..50)).collect();
+        let mut graph = vec![Vec::new(); n];
+        for _ in 0..800 {
+            let u = rng.random_range(0..n);
+            let v = rng.random_range(0..n);
+            let base = rng.random_range(0..20);
+            graph[u].push(Edge::new(v, (base + h[v] - h[u]) as Length));
+        }
+
+        let mut expected = vec![Length::INFINITY; n];
+        expected[0] = 0.0;
+        for _ in 0..n {
+            for (u, edges) in graph.iter().enumerate() {
+                for edge in edges {
+                    let v = *edge.vertex();
+                    expected[v] = expected[v].min(expected[u] + edge.length());
+                }
+            }
+        }
+
+        let mut johnson = Johnson::new(&graph).unwrap();
+        assert_eq!(johnson.get(0), expected);
+    }
+
+    #[test]
+    fn test_johnson_reports_negative_cycle() {
+        let mut graph = vec![Vec::new(); 4];
+        graph[0].push(Edge::new(1, 1.0));
+        graph[1].push(Edge::new(2, -2.0));
+        graph[2].push(Edge::new(3, 1.0));
+        graph[3].push(Edge::new(1, 0.5));
+
+        let cycle = Johnson::new(&graph).unwrap_err();
+        let mut vertices = cycle.vertices.clone();
+        vertices.sort();
+        assert_eq!(vertices, vec![1, 2, 3]);
+        assert_eq!(cycle.length, -0.5);
+    }
+}
@@ -1,13 +1,138 @@
+pub mod dimacs;
+
+mod alt;
+mod binary;
+mod contraction;
+mod dijkstra;
+mod dynamic;
+mod error;
+mod filter;
 mod heaps;
+mod heuristic;
+mod hub_labels;
+mod johnson;
 mod models;
+mod negative_cycle;
+mod oracle;
+mod profiles;
+mod sets;
 mod shortest_path;
+mod turns;
 
-pub use models::{Edge, Graph};
-pub use shortest_path::ShortestPath;
+pub use alt::{Landmarks, Selection};
+pub use contraction::ContractionHierarchy;
+pub use dijkstra::dijkstra;
+pub use dynamic::DynamicShortestPath;
+pub use error::{GraphError, NegativeCycle, WeightError};
+pub use filter::{EdgePredicate, Filter};
+pub use heaps::QueueKind;
+pub use heuristic::{Coordinates, Heuristic, Metric, Point};
+pub use hub_labels::{HubLabels, LabelStats};
+pub use johnson::Johnson;
+pub use models::{Edge, Graph, Weight};
+pub use negative_cycle::{CycleCheck, Potentials, find_negative_cycle};
+pub use oracle::DistanceOracle;
+pub use profiles::{MultiGraph, Profile};
+pub use sets::BitSet;
+pub use shortest_path::{Meeting, PathDag, Route, ShortestPath, Voronoi};
+pub use turns::{TurnCosts, TurnGraph};
+
+/// Graphs and reference answers shared by the tests of every module.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use crate::models::{Edge, Length, Vertex};
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    pub(crate) fn random_graph(n: usize, m: usize, seed: u64) -> Vec<Vec<Edge>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut graph = vec![Vec::new(); n];
+        for _ in 0..m {
+            let u = rng.random_range(0..n);
+            let v = rng.random_range(0..n);
+            graph[u].push(Edge::new(v, rng.random_range(1..100) as f32));
+        }
+        graph
+    }
+
+    /// `side` x `side` grid, vertex `y * side + x`, with edges both ways between
+    /// horizontal and vertical neighbours; `u -> v` has length `length(u, v)`.
+    pub(crate) fn grid(
+        side: usize,
+        mut length: impl FnMut(Vertex, Vertex) -> Length,
+    ) -> Vec<Vec<Edge>> {
+        let id = |x: usize, y: usize| y * side + x;
+        let mut graph = vec![Vec::new(); side * side];
+        for y in 0..side {
+            for x in 0..side {
+                for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+                    if nx < side && ny < side {
+                        let (u, v) = (id(x, y), id(nx, ny));
+                        graph[u].push(Edge::new(v, length(u, v)));
+                        graph[v].push(Edge::new(u, length(v, u)));
+                    }
+                }
+            }
+        }
+        graph
+    }
+
+    /// [`grid`] with a random integer length in `1..10` per direction.
+    pub(crate) fn random_grid(side: usize, seed: u64) -> Vec<Vec<Edge>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        grid(side, |_, _| rng.random_range(1..10) as Length)
+    }
+
+    /// The six-vertex example of the Dijkstra article on Wikipedia, from 0.
+    pub(crate) fn sample() -> Vec<Vec<Edge>> {
+        let mut graph = vec![Vec::new(); 6];
+        graph[0].push(Edge::new(1, 7.0));
+        graph[0].push(Edge::new(2, 9.0));
+        graph[0].push(Edge::new(5, 14.0));
+        graph[1].push(Edge::new(2, 10.0));
+        graph[1].push(Edge::new(3, 15.0));
+        graph[2].push(Edge::new(3, 11.0));
+        graph[2].push(Edge::new(5, 2.0));
+        graph[3].push(Edge::new(4, 6.0));
+        graph[5].push(Edge::new(4, 9.0));
+        graph
+    }
+
+    pub(crate) fn dijkstra(graph: &[Vec<Edge>], s: usize) -> Vec<f32> {
+        let mut dist = vec![f32::INFINITY; graph.len()];
+        let mut done = vec![false; graph.len()];
+        dist[s] = 0.0;
+        while let Some(u) = (0..graph.len())
+            .filter(|&u| !done[u] && dist[u].is_finite())
+            .min_by(|&a, &b| dist[a].total_cmp(&dist[b]))
+        {
+            done[u] = true;
+            for edge in &graph[u] {
+                let v = *edge.vertex();
+                dist[v] = dist[v].min(dist[u] + edge.length());
+            }
+        }
+        dist
+    }
+
+    /// Length of `path`, taking the shortest of parallel edges.
+    pub(crate) fn path_length(graph: &[Vec<Edge>], path: &[Vertex]) -> Length {
+        path.windows(2)
+            .map(|pair| {
+                graph[pair[0]]
+                    .iter()
+                    .filter(|edge| *edge.vertex() == pair[1])
+                    .map(|edge| *edge.length())
+                    .fold(Length::INFINITY, Length::min)
+            })
+            .sum()
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fixtures::{dijkstra, path_length, random_graph};
+    use rand::{Rng, SeedableRng, rngs::StdRng};
 
     // AOJ GRL_1_A (https://judge.u-aizu.ac.jp/onlinejudge/description.jsp?id=GRL_1_A)
     #[test]
@@ -48,4 +173,487 @@ mod tests {
         assert_eq!(distances[2], 2.0);
         assert_eq!(distances[3], f32::INFINITY);
     }
+
+    #[test]
+    fn test_bmssp_matches_dijkstra() {
+        for seed in 0..20 {
+            let graph = random_graph(300, 1200, seed);
+            let expected = dijkstra(&graph, 0);
+
+            let mut sp = ShortestPath::new(graph);
+            assert_eq!(sp.get(0), expected, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_reused_solver_matches_dijkstra() {
+        let graph = random_graph(300, 1200, 29);
+        let mut sp = ShortestPath::new(graph.clone());
+        for s in [0, 299, 150, 0] {
+            assert_eq!(sp.get(s), dijkstra(&graph, s), "source {s}");
+        }
+    }
+
+    #[test]
+    fn test_multi_source_matches_dijkstra() {
+        for seed in 0..10 {
+            let graph = random_graph(300, 1200, seed);
+            let sources = [3, 77, 150, 299];
+            let per_source: Vec<_> = sources.iter().map(|&s| dijkstra(&graph, s)).collect();
+
+            let mut sp = ShortestPath::new(graph);
+            let distances = sp.get_from(&sources, None);
+            let nearest = sp.nearest_sources();
+            for v in 0..300 {
+                let expected = per_source
+                    .iter()
+                    .map(|d| d[v])
+                    .fold(f32::INFINITY, f32::min);
+                assert_eq!(distances[v], expected, "seed {seed} vertex {v}");
+                match nearest[v] {
+                    Some(s) => assert_eq!(
+                        per_source[sources.iter().position(|&x| x == s).unwrap()][v],
+                        expected
+                    ),
+                    None => assert_eq!(expected, f32::INFINITY),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_integer_queues_match_dijkstra() {
+        for seed in 0..5 {
+            let graph = random_graph(300, 1200, seed);
+            let expected = dijkstra(&graph, 0);
+
+            for kind in [QueueKind::DAry, QueueKind::Radix, QueueKind::Dial] {
+                let graph = Graph::from(graph.clone());
+                assert_eq!(crate::dijkstra(&graph, 0, kind), expected, "{kind:?}");
+
+                let mut sp = ShortestPath::new(graph).with_queue(kind);
+                assert_eq!(sp.get(0), expected, "{kind:?} seed {seed}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_unit_weights_use_bfs() {
+        let mut graph = random_graph(300, 1200, 7);
+        for edge in graph.iter_mut().flatten() {
+            *edge = Edge::new(*edge.vertex(), 1.0);
+        }
+        let expected = dijkstra(&graph, 0);
+
+        let mut sp = ShortestPath::new(graph.clone());
+        assert_eq!(sp.get(0), expected);
+
+        let mut sp = ShortestPath::new(graph).with_unit_weights(false);
+        assert_eq!(sp.get(0), expected);
+    }
+
+    #[test]
+    fn test_forced_unit_weights_count_hops() {
+        let mut graph = vec![Vec::new(); 3];
+        graph[0].push(Edge::new(1, 5.0));
+        graph[1].push(Edge::new(2, 5.0));
+        graph[0].push(Edge::new(2, 20.0));
+
+        let mut sp = ShortestPath::new(graph).with_unit_weights(true);
+        assert_eq!(sp.get(0), vec![0.0, 1.0, 1.0]);
+    }
+
+    fn path_graph(n: usize, length: f32) -> Vec<Vec<Edge>> {
+        (0..n)
+            .map(|u| {
+                (u + 1 < n)
+                    .then(|| Edge::new(u + 1, length))
+                    .into_iter()
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Runs on a thread with a deliberately small stack: nothing may recurse per
+    // vertex or per tree level.
+    #[test]
+    fn test_million_vertex_path_small_stack() {
+        let n = 1_000_000;
+        let distances = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(move || ShortestPath::new(path_graph(n, 2.0)).get(0))
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert_eq!(distances[n - 1], 2.0 * (n - 1) as f32);
+    }
+
+    #[test]
+    fn test_approximation_within_epsilon() {
+        let mut rng = StdRng::seed_from_u64(41);
+        for seed in 0..20 {
+            let epsilon = [0.01, 0.1, 0.5, 2.0][seed as usize % 4];
+            let mut graph = random_graph(300, 1200, seed);
+            for edge in graph.iter_mut().flatten() {
+                *edge = Edge::new(*edge.vertex(), rng.random_range(0.0..50.0));
+            }
+            let s = rng.random_range(0..300);
+            let exact = ShortestPath::new(graph.clone()).get(s);
+
+            let mut sp = ShortestPath::new(graph.clone()).with_approximation(epsilon);
+            let approx = sp.get(s);
+            for (v, (&d, &a)) in exact.iter().zip(&approx).enumerate() {
+                if d == f32::INFINITY {
+                    assert_eq!(a, d, "seed {seed}");
+                    continue;
+                }
+                // NOTE: the tolerance only absorbs f32 summation error
+                let tolerance = 1e-5 * d;
+                assert!(d - tolerance <= a, "seed {seed}: {a} < {d}");
+                assert!(
+                    a <= (1.0 + epsilon as f32) * d + tolerance,
+                    "seed {seed}: {a} vs {d}"
+                );
+
+                let length = path_length(&graph, &sp.path(v).unwrap());
+                assert!(length <= a + tolerance, "seed {seed}: path {length} > {a}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_dynamic_updates_match_dijkstra() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for seed in 0..5 {
+            let mut graph = random_graph(200, 800, seed);
+            let mut dsp = DynamicShortestPath::new(graph.clone(), 0);
+            for step in 0..100 {
+                let u = rng.random_range(0..200);
+                let weight = Weight::new(rng.random_range(0..100) as f32).unwrap();
+                match (
+                    rng.random_range(0..3),
+                    graph[u].first().map(|e| *e.vertex()),
+                ) {
+                    (0, Some(v)) => {
+                        graph[u].remove(0);
+                        dsp.delete_edge(u, v);
+                    }
+                    (1, Some(v)) => {
+                        graph[u][0] = Edge::new(v, weight.get());
+                        dsp.update_weight(u, v, weight);
+                    }
+                    _ => {
+                        let v = rng.random_range(0..200);
+                        graph[u].push(Edge::new(v, weight.get()));
+                        dsp.insert_edge(u, v, weight);
+                    }
+                }
+                assert_eq!(
+                    dsp.distances(),
+                    dijkstra(&graph, 0),
+                    "seed {seed} step {step}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_filters_match_dijkstra_on_pruned_graph() {
+        let mut rng = StdRng::seed_from_u64(43);
+        for seed in 0..10 {
+            let graph = random_graph(300, 1200, seed);
+            let blocked: BitSet = (0..30).map(|_| rng.random_range(1..300)).collect();
+            // NOTE: depends on the direction, so reverse searches must see forward edges
+            let closed = |u: usize, edge: &Edge| *edge.length() < 80.0 || u < *edge.vertex();
+
+            // a blocked vertex is never entered, nor left as a source
+            let mut pruned = graph.clone();
+            for (u, edges) in pruned.iter_mut().enumerate() {
+                edges.retain(|edge| {
+                    !blocked.contains(u) && !blocked.contains(*edge.vertex()) && closed(u, edge)
+                });
+            }
+            let expected = dijkstra(&pruned, 0);
+            let to_source = dijkstra(&Graph::from(pruned).reversed(), 0);
+
+            let filter = Filter::new().with_blocked(&blocked).with_edges(&closed);
+            let filter = Some(&filter);
+            let mut sp = ShortestPath::new(graph.clone());
+            assert_eq!(sp.get_from(&[0], filter), expected, "seed {seed}");
+            assert_eq!(sp.get_to(0, filter), to_source, "seed {seed}");
+            assert_eq!(sp.get_hop_limited(0, 299, filter), expected, "seed {seed}");
+
+            let flat = Coordinates::new(vec![(0.0, 0.0); 300], Metric::Euclidean);
+            for t in (0..300).step_by(7) {
+                let length = expected[t].is_finite().then_some(expected[t]);
+                let meeting = sp.bidirectional(0, t, filter);
+                assert_eq!(meeting.map(|m| m.length), length, "seed {seed} target {t}");
+                let route = sp.astar(0, t, &flat, filter);
+                assert_eq!(route.map(|r| r.length), length, "seed {seed} target {t}");
+            }
+            let reached = expected.iter().filter(|d| d.is_finite()).count();
+            assert_eq!(sp.nearest(0, 300, |_| true, filter).len(), reached);
+
+            // the graph is untouched
+            assert_eq!(sp.get(0), dijkstra(&graph, 0), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_vertex_weights_match_split_graph() {
+        let mut rng = StdRng::seed_from_u64(45);
+        for seed in 0..10 {
+            let n = 300;
+            let graph = random_graph(n, 1200, seed);
+            let costs: Vec<f32> = (0..n).map(|_| rng.random_range(0..20) as f32).collect();
+
+            // v becomes v (entry) -> n + v (exit), and u -> v leaves from n + u
+            let mut split = vec![Vec::new(); 2 * n];
+            for (u, edges) in graph.iter().enumerate() {
+                split[u].push(Edge::new(n + u, costs[u]));
+                for edge in edges {
+                    split[n + u].push(Edge::new(*edge.vertex(), *edge.length()));
+                }
+            }
+            let mut expected = dijkstra(&split, n)[..n].to_vec();
+            expected[0] = 0.0;
+
+            let landmarks = Landmarks::select(
+                &mut ShortestPath::new(graph.clone()),
+                4,
+                Selection::Farthest,
+            );
+            let mut sp = ShortestPath::new(graph).with_vertex_weights(costs);
+            assert_eq!(sp.get(0), expected, "seed {seed}");
+            for t in (0..n).step_by(7).filter(|&t| expected[t].is_finite()) {
+                let meeting = sp.bidirectional(0, t, None).unwrap();
+                assert_eq!(meeting.length, expected[t], "seed {seed} target {t}");
+                let route = sp.astar(0, t, &landmarks, None).unwrap();
+                assert_eq!(route.length, expected[t], "seed {seed} target {t}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_hop_limited_matches_bellman_ford() {
+        for seed in 0..5 {
+            let graph = random_graph(200, 800, seed);
+            let mut sp = ShortestPath::new(graph.clone());
+            let mut expected = vec![f32::INFINITY; 200];
+            expected[0] = 0.0;
+            for hops in 0..12 {
+                assert_eq!(
+                    sp.get_hop_limited(0, hops, None),
+                    expected,
+                    "seed {seed} hops {hops}"
+                );
+                let mut next = expected.clone();
+                for (u, edges) in graph.iter().enumerate() {
+                    for edge in edges {
+                        let v = *edge.vertex();
+                        next[v] = next[v].min(expected[u] + edge.length());
+                    }
+                }
+                expected = next;
+            }
+            assert_eq!(
+                sp.get_hop_limited(0, 199, None),
+                dijkstra(&graph, 0),
+                "seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nearest_matches_sorted_distances() {
+        for seed in 0..5 {
+            let graph = random_graph(300, 1200, seed);
+            let distances = dijkstra(&graph, 0);
+            let mut sp = ShortestPath::new(graph);
+
+            for k in [0, 1, 10, 300] {
+                let nearest = sp.nearest(0, k, |v| v % 3 == 0, None);
+                let mut expected: Vec<f32> = (0..300)
+                    .filter(|v| v % 3 == 0 && distances[*v].is_finite())
+                    .map(|v| distances[v])
+                    .collect();
+                expected.sort_by(f32::total_cmp);
+                expected.truncate(k);
+
+                let found: Vec<f32> = nearest.iter().map(|&(_, d)| d).collect();
+                assert_eq!(found, expected, "seed {seed} k {k}");
+                for &(v, d) in &nearest {
+                    assert_eq!((v % 3, d), (0, distances[v]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_voronoi_regions_are_closest() {
+        for seed in 0..5 {
+            let graph = random_graph(300, 1200, seed);
+            let sources = [0, 100, 200];
+            let per_source: Vec<_> = sources.iter().map(|&s| dijkstra(&graph, s)).collect();
+
+            let voronoi = ShortestPath::new(graph.clone()).voronoi(&sources);
+            for (v, &(d, region)) in voronoi.cells.iter().enumerate() {
+                let closest = per_source
+                    .iter()
+                    .map(|dist| dist[v])
+                    .fold(f32::INFINITY, f32::min);
+                assert_eq!(d, closest, "seed {seed}");
+                if let Some(s) = region {
+                    let i = sources.iter().position(|&x| x == s).unwrap();
+                    assert_eq!(per_source[i][v], closest, "seed {seed}");
+                }
+            }
+            // every edge leaving a region, in graph order, and nothing else
+            for &s in &sources {
+                let expected: Vec<_> = graph
+                    .iter()
+                    .enumerate()
+                    .filter(|&(u, _)| voronoi.region(u) == Some(s))
+                    .flat_map(|(u, edges)| edges.iter().map(move |edge| (u, *edge.vertex())))
+                    .filter(|&(_, v)| voronoi.region(v) != Some(s))
+                    .collect();
+                assert_eq!(voronoi.boundary[&s], expected, "seed {seed} source {s}");
+            }
+            assert_eq!(voronoi.boundary.len(), sources.len());
+        }
+    }
+
+    #[test]
+    fn test_zero_weight_cycles() {
+        // 0 -> 1 -> 2 -> 1 with a zero-weight cycle between 1 and 2, plus self-loops
+        let mut graph = vec![Vec::new(); 4];
+        graph[0].push(Edge::new(1, 3.0));
+        graph[0].push(Edge::new(0, 0.0));
+        graph[1].push(Edge::new(2, 0.0));
+        graph[2].push(Edge::new(1, 0.0));
+        graph[2].push(Edge::new(2, 0.0));
+        graph[2].push(Edge::new(3, 1.0));
+
+        let mut sp = ShortestPath::new(graph);
+        assert_eq!(sp.get(0), vec![0.0, 3.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_zero_weights_match_dijkstra() {
+        for seed in 0..10 {
+            let mut graph = random_graph(300, 1200, seed);
+            for edge in graph.iter_mut().flatten() {
+                *edge = Edge::new(*edge.vertex(), (edge.length() % 3.0).floor());
+            }
+            let expected = dijkstra(&graph, 0);
+
+            let mut sp = ShortestPath::new(graph);
+            assert_eq!(sp.get(0), expected, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_weights_are_rejected() {
+        let graph = vec![vec![Edge::new(1, f32::NAN)], Vec::new()];
+        let err = Graph::new(graph).unwrap_err();
+        assert_eq!((err.from, err.to, err.error), (0, 1, WeightError::NaN));
+    }
+
+    #[test]
+    fn test_paths_follow_tree() {
+        let graph = random_graph(300, 1200, 11);
+        let mut sp = ShortestPath::new(graph.clone());
+        let distances = sp.get(0);
+
+        for (v, &d) in distances.iter().enumerate() {
+            let Some(path) = sp.path(v) else {
+                assert_eq!(d, f32::INFINITY);
+                continue;
+            };
+            assert_eq!((path[0], path[path.len() - 1]), (0, v));
+            assert_eq!(path_length(&graph, &path), d);
+        }
+    }
+
+    #[test]
+    fn test_get_to_matches_forward_queries() {
+        let graph = random_graph(200, 800, 5);
+        let target = 7;
+        let mut sp = ShortestPath::new(graph.clone());
+        let to_target = sp.get_to(target, None);
+
+        for (v, &d) in to_target.iter().enumerate() {
+            assert_eq!(d, dijkstra(&graph, v)[target], "from {v}");
+            match sp.path_from(v) {
+                Some(path) => assert_eq!((path[0], path[path.len() - 1]), (v, target)),
+                None => assert_eq!(d, f32::INFINITY),
+            }
+        }
+    }
+
+    #[test]
+    fn test_contraction_hierarchy_matches_dijkstra() {
+        for seed in 0..5 {
+            let graph = random_graph(300, 1200, seed);
+            let mut ch = ContractionHierarchy::new(graph.clone());
+            for s in [0, 150] {
+                let expected = dijkstra(&graph, s);
+                for (t, &d) in expected.iter().enumerate() {
+                    let route = ch.query(s, t);
+                    assert_eq!(route.map_or(f32::INFINITY, |r| r.length), d, "seed {seed}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hub_labels_match_dijkstra() {
+        for seed in 0..5 {
+            let graph = random_graph(300, 1200, seed);
+            let labels = HubLabels::new(graph.clone());
+            for s in [0, 150] {
+                for (t, &d) in dijkstra(&graph, s).iter().enumerate() {
+                    assert_eq!(labels.distance(s, t), d, "seed {seed}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_oracle_respects_stretch() {
+        let mut graph = random_graph(300, 1200, 17);
+        for u in 0..graph.len() {
+            for edge in graph[u].clone() {
+                graph[*edge.vertex()].push(Edge::new(u, *edge.length()));
+            }
+        }
+        let exact: Vec<_> = [0, 42, 299].iter().map(|&s| dijkstra(&graph, s)).collect();
+        for k in 1..=4 {
+            let oracle = DistanceOracle::new(graph.clone(), k);
+            let stretch = oracle.stretch() as f32;
+            for (&s, expected) in [0, 42, 299].iter().zip(&exact) {
+                for (t, &d) in expected.iter().enumerate() {
+                    let estimate = oracle.distance(s, t);
+                    assert!(
+                        d <= estimate && estimate <= stretch * d,
+                        "k {k}: {s} -> {t}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bidirectional_matches_dijkstra() {
+        let graph = random_graph(300, 1200, 13);
+        let mut sp = ShortestPath::new(graph.clone());
+        for s in [0, 17, 123] {
+            let expected = dijkstra(&graph, s);
+            for (t, &d) in expected.iter().enumerate() {
+                let meeting = sp.bidirectional(s, t, None);
+                assert_eq!(meeting.map_or(f32::INFINITY, |m| m.length), d);
+            }
+        }
+    }
 }
@@ -1,9 +1,48 @@
 use std::{cmp::Ordering, ops::Deref};
 
+use crate::error::{GraphError, WeightError};
+
 pub type Vertex = usize;
 
 pub type Length = f32;
 
+/// A length known to be finite and non-negative, hence totally ordered.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Weight(Length);
+
+impl Weight {
+    pub fn new(length: Length) -> Result<Self, WeightError> {
+        if length.is_nan() {
+            Err(WeightError::NaN)
+        } else if length.is_infinite() {
+            Err(WeightError::Infinite)
+        } else if length < 0.0 {
+            Err(WeightError::Negative(length))
+        } else {
+            // NOTE: -0.0 is normalised so that it equals 0.0 under `total_cmp`
+            Ok(Self(length + 0.0))
+        }
+    }
+
+    pub fn get(self) -> Length {
+        self.0
+    }
+}
+
+impl Eq for Weight {}
+
+impl Ord for Weight {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl PartialOrd for Weight {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 //NOTE: clone can be avoided
 #[derive(Debug, Default, PartialEq, Clone)]
 pub struct Edge {
@@ -15,6 +54,10 @@ impl Edge {
     pub fn new(vertex: Vertex, length: Length) -> Self {
         Self { vertex, length }
     }
+    /// Like [`Edge::new`], but rejects NaN, infinite and negative lengths.
+    pub fn try_new(vertex: Vertex, length: Length) -> Result<Self, WeightError> {
+        Weight::new(length).map(|weight| Self::new(vertex, weight.get()))
+    }
     pub fn vertex(&self) -> &Vertex {
         &self.vertex
     }
@@ -28,8 +71,7 @@ impl Eq for Edge {}
 impl Ord for Edge {
     fn cmp(&self, other: &Self) -> Ordering {
         self.length
-            .partial_cmp(&other.length)
-            .unwrap_or(Ordering::Equal)
+            .total_cmp(&other.length)
             .then_with(|| self.vertex.cmp(&other.vertex))
     }
 }
@@ -40,9 +82,107 @@ impl PartialOrd for Edge {
     }
 }
 
+/// Adjacency lists whose edge weights are all finite and non-negative.
+///
+/// Both constructors check every weight and the lengths are never mutated
+/// without a [`Weight`], so an invalid graph cannot exist. Zero weights are
+/// allowed, including zero-weight cycles.
 #[derive(Debug, Default, Clone)]
 pub struct Graph(Vec<Vec<Edge>>);
 
+impl Graph {
+    /// Builds a graph, rejecting NaN, infinite and negative edge weights.
+    pub fn new(graph: Vec<Vec<Edge>>) -> Result<Self, GraphError> {
+        for (from, edges) in graph.iter().enumerate() {
+            for edge in edges {
+                Weight::new(edge.length).map_err(|error| GraphError {
+                    from,
+                    to: edge.vertex,
+                    error,
+                })?;
+            }
+        }
+        Ok(Self(graph))
+    }
+
+    /// The transpose: every edge `u -> v` becomes `v -> u` with the same length.
+    pub fn reversed(&self) -> Self {
+        let mut reversed = vec![Vec::new(); self.len()];
+        for (u, edges) in self.iter().enumerate() {
+            for edge in edges {
+                reversed[edge.vertex].push(Edge::new(u, edge.length));
+            }
+        }
+        Self(reversed)
+    }
+
+    /// Whether every edge length is a non-negative integer, as required by the
+    /// radix and bucket queues.
+    pub fn has_integer_weights(&self) -> bool {
+        self.iter()
+            .flatten()
+            .all(|edge| edge.length >= 0.0 && edge.length.fract() == 0.0)
+    }
+
+    /// Rounds every positive length up to the next power of `1 + epsilon`, so
+    /// lengths grow by less than that factor and take few distinct values.
+    pub fn rounded(&self, epsilon: f64) -> Self {
+        let base = (1.0 + epsilon).ln();
+        let round = |length: Length| {
+            if length == 0.0 {
+                return length;
+            }
+            let length = f64::from(length);
+            let mut up = (1.0 + epsilon).powf((length.ln() / base).ceil());
+            // NOTE: `ln` may land a hair below an exact power
+            if up < length {
+                up *= 1.0 + epsilon;
+            }
+            up as Length
+        };
+        Self(
+            self.iter()
+                .map(|edges| {
+                    edges
+                        .iter()
+                        .map(|edge| Edge::new(edge.vertex, round(edge.length)))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    /// The largest edge length, `0.0` for a graph without edges.
+    pub fn max_weight(&self) -> Length {
+        self.iter()
+            .flatten()
+            .map(|edge| edge.length)
+            .fold(0.0, Length::max)
+    }
+
+    /// Whether every edge has length `1.0`, so hop counts are distances.
+    pub fn has_unit_weights(&self) -> bool {
+        self.iter().flatten().all(|edge| edge.length == 1.0)
+    }
+
+    pub fn insert_edge(&mut self, u: Vertex, v: Vertex, weight: Weight) {
+        self.0[u].push(Edge::new(v, weight.get()));
+    }
+
+    /// Sets the length of the first `u -> v` edge, returning the old length,
+    /// or `None` if there is no such edge.
+    pub fn update_weight(&mut self, u: Vertex, v: Vertex, weight: Weight) -> Option<Length> {
+        let edge = self.0[u].iter_mut().find(|edge| edge.vertex == v)?;
+        Some(std::mem::replace(&mut edge.length, weight.get()))
+    }
+
+    /// Removes the first `u -> v` edge, returning its length.
+    pub fn delete_edge(&mut self, u: Vertex, v: Vertex) -> Option<Length> {
+        let i = self.0[u].iter().position(|edge| edge.vertex == v)?;
+        Some(self.0[u].remove(i).length)
+    }
+}
+
 impl Deref for Graph {
     type Target = Vec<Vec<Edge>>;
 
@@ -51,8 +191,78 @@ impl Deref for Graph {
     }
 }
 
+/// # Panics
+/// If an edge weight is NaN, infinite or negative; [`Graph::new`] returns the
+/// error instead.
 impl From<Vec<Vec<Edge>>> for Graph {
     fn from(graph: Vec<Vec<Edge>>) -> Self {
-        Self(graph)
+        Self::new(graph).unwrap_or_else(|err| panic!("invalid graph: {err}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph_rejects_invalid_weights() {
+        for (length, error) in [
+            (Length::NAN, WeightError::NaN),
+            (Length::INFINITY, WeightError::Infinite),
+            (-1.0, WeightError::Negative(-1.0)),
+        ] {
+            let graph = vec![vec![Edge::new(1, 1.0)], vec![Edge::new(0, length)]];
+            let expected = GraphError {
+                from: 1,
+                to: 0,
+                error,
+            };
+            assert_eq!(Graph::new(graph).unwrap_err(), expected);
+        }
+
+        let graph = vec![vec![Edge::new(1, 0.0)], vec![Edge::new(0, -0.0)]];
+        assert!(Graph::new(graph).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "edge 0 -> 1: weight -2 is negative")]
+    fn test_graph_from_rejects_invalid_weights() {
+        let _ = Graph::from(vec![vec![Edge::new(1, -2.0)], Vec::new()]);
+    }
+
+    #[test]
+    fn test_graph_reversed() {
+        let graph = Graph::from(vec![
+            vec![Edge::new(1, 1.0), Edge::new(2, 4.0)],
+            vec![Edge::new(2, 2.0)],
+            Vec::new(),
+        ]);
+        let reversed = graph.reversed();
+        assert_eq!(reversed[0], vec![]);
+        assert_eq!(reversed[1], vec![Edge::new(0, 1.0)]);
+        assert_eq!(reversed[2], vec![Edge::new(0, 4.0), Edge::new(1, 2.0)]);
+    }
+
+    #[test]
+    fn test_weight_total_order() {
+        let mut weights: Vec<_> = [3.0, 0.0, -0.0, 1.5]
+            .into_iter()
+            .map(|l| Weight::new(l).unwrap())
+            .collect();
+        weights.sort();
+        assert_eq!(weights[0], weights[1]);
+        assert_eq!(weights[3].get(), 3.0);
+    }
+
+    #[test]
+    fn test_graph_rounded() {
+        let lengths = [0.0, 1.0, 1.5, 2.0, 3.0, 1000.0];
+        let graph = Graph::from(vec![lengths.iter().map(|&l| Edge::new(0, l)).collect()]);
+        let rounded: Vec<_> = graph.rounded(1.0)[0].iter().map(|e| *e.length()).collect();
+        assert_eq!(rounded, [0.0, 1.0, 2.0, 2.0, 4.0, 1024.0]);
+
+        for (&original, edge) in lengths.iter().zip(&graph.rounded(0.1)[0]) {
+            assert!(original <= edge.length && edge.length <= 1.1 * original);
+        }
     }
 }
@@ -0,0 +1,193 @@
+use std::ops::Deref;
+
+use crate::{
+    error::NegativeCycle,
+    models::{Edge, Vertex},
+};
+
+/// Outcome of [`find_negative_cycle`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum CycleCheck {
+    Negative(NegativeCycle),
+    /// No negative cycle exists, certified by feasible potentials.
+    Feasible(Potentials),
+}
+
+/// Vertex potentials `h` with `h(v) <= h(u) + w(u, v)` on every edge.
+///
+/// Summing that inequality around any cycle shows the cycle is non-negative,
+/// so feasible potentials prove there is no negative cycle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Potentials(Vec<f64>);
+
+impl Potentials {
+    /// Checks the certificate against `graph`.
+    pub fn verify(&self, graph: &[Vec<Edge>]) -> bool {
+        self.len() == graph.len()
+            && graph.iter().enumerate().all(|(u, edges)| {
+                edges
+                    .iter()
+                    .all(|edge| self[*edge.vertex()] <= self[u] + *edge.length() as f64)
+            })
+    }
+}
+
+impl Deref for Potentials {
+    type Target = [f64];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Looks for a negative cycle anywhere in `graph`. Takes plain adjacency lists
+/// since a [`crate::Graph`] cannot hold negative weights.
+///
+/// Runs Bellman-Ford from a virtual source with a zero-length edge to every
+/// vertex, in rounds over a FIFO queue. Once at least `n` relaxations have
+/// happened since the last check, the predecessor graph is checked for a
+/// cycle, which can only be a negative one. A check costs O(n), so it adds
+/// O(1) per relaxation, and a cycle is reported soon after it shows up instead
+/// of after `n` rounds.
+///
+/// # Panics
+/// If an edge weight is NaN or infinite.
+pub fn find_negative_cycle(graph: &[Vec<Edge>]) -> CycleCheck {
+    assert!(
+        graph.iter().flatten().all(|edge| edge.length().is_finite()),
+        "edge weights must be finite"
+    );
+
+    let n = graph.len();
+    let mut h = vec![0.0; n];
+    // (tail, index in the tail's adjacency) of the edge that last improved v
+    let mut pred: Vec<Option<(Vertex, usize)>> = vec![None; n];
+    let mut queued = vec![true; n];
+    let mut current: Vec<Vertex> = (0..n).collect();
+    let mut next = Vec::new();
+    // relaxations since the predecessor graph was last checked
+    let mut unchecked = 0;
+
+    while !current.is_empty() {
+        for &u in &current {
+            queued[u] = false;
+            for (i, edge) in graph[u].iter().enumerate() {
+                let v = *edge.vertex();
+                let new_dist = h[u] + *edge.length() as f64;
+                if new_dist < h[v] {
+                    h[v] = new_dist;
+                    pred[v] = Some((u, i));
+                    unchecked += 1;
+                    if !queued[v] {
+                        queued[v] = true;
+                        next.push(v);
+                    }
+                }
+            }
+        }
+
+        if unchecked >= n {
+            unchecked = 0;
+            if let Some(cycle) = find_cycle(graph, &pred) {
+                return CycleCheck::Negative(cycle);
+            }
+        }
+        std::mem::swap(&mut current, &mut next);
+        next.clear();
+    }
+
+    CycleCheck::Feasible(Potentials(h))
+}
+
+/// Finds a cycle in the predecessor graph, if any.
+fn find_cycle(graph: &[Vec<Edge>], pred: &[Option<(Vertex, usize)>]) -> Option<NegativeCycle> {
+    // 0 = unvisited, otherwise 1 + the vertex whose walk first reached it
+    let mut walk = vec![0; pred.len()];
+    for start in 0..pred.len() {
+        let mut v = start;
+        while walk[v] == 0 {
+            walk[v] = start + 1;
+            match pred[v] {
+                Some((u, _)) => v = u,
+                None => break,
+            }
+        }
+        if walk[v] != start + 1 || pred[v].is_none() {
+            continue;
+        }
+
+        // v is on the cycle; walk it backwards once, then flip to edge order
+        let mut vertices = Vec::new();
+        let mut edges = Vec::new();
+        let mut u = v;
+        while let Some((tail, i)) = pred[u] {
+            vertices.push(tail);
+            edges.push(graph[tail][i].clone());
+            u = tail;
+            if u == v {
+                break;
+            }
+        }
+        vertices.reverse();
+        edges.reverse();
+        return Some(NegativeCycle::new(vertices, edges));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_negative_cycle_edges() {
+        let mut graph = vec![Vec::new(); 4];
+        graph[0].push(Edge::new(1, 1.0));
+        graph[1].push(Edge::new(2, 5.0));
+        graph[1].push(Edge::new(2, -2.0));
+        graph[2].push(Edge::new(3, 1.0));
+        graph[3].push(Edge::new(1, 0.5));
+
+        let CycleCheck::Negative(cycle) = find_negative_cycle(&graph) else {
+            panic!("expected a negative cycle");
+        };
+        assert_eq!(cycle.length, -0.5);
+        assert_eq!(cycle.vertices.len(), 3);
+        for (i, edge) in cycle.edges.iter().enumerate() {
+            let next = cycle.vertices[(i + 1) % cycle.vertices.len()];
+            assert_eq!(*edge.vertex(), next);
+        }
+        assert!(cycle.edges.contains(&Edge::new(2, -2.0)));
+    }
+
+    #[test]
+    fn test_find_negative_cycle_certificate() {
+        let mut graph = vec![Vec::new(); 3];
+        graph[0].push(Edge::new(1, -1.0));
+        graph[1].push(Edge::new(2, -1.0));
+        graph[2].push(Edge::new(0, 2.0));
+
+        let CycleCheck::Feasible(potentials) = find_negative_cycle(&graph) else {
+            panic!("zero-length cycle is not negative");
+        };
+        assert!(potentials.verify(&graph));
+        assert!(!Potentials(vec![0.0; 3]).verify(&graph));
+    }
+
+    #[test]
+    fn test_long_chain_among_isolated_vertices() {
+        // each round only moves down a chain of 1000 negative edges, so checking
+        // all n vertices after every round would dominate
+        let n = 1_000_000;
+        let mut graph = vec![Vec::new(); n];
+        for (u, edges) in graph.iter_mut().enumerate().take(1000).skip(1) {
+            edges.push(Edge::new(u - 1, -1.0));
+        }
+
+        let CycleCheck::Feasible(potentials) = find_negative_cycle(&graph) else {
+            panic!("a chain has no cycle");
+        };
+        assert_eq!(potentials[0], -999.0);
+        assert!(potentials.verify(&graph));
+    }
+}
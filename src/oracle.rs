@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use crate::{
+    ShortestPath,
+    models::{Edge, Graph, Length, Vertex},
+    shortest_path::search::Search,
+};
+
+/// Thorup–Zwick approximate distance oracle.
+///
+/// The vertices are sampled into a hierarchy `V = A_0 ⊇ A_1 ⊇ … ⊇ A_{k-1}`,
+/// each level keeping a vertex with probability `n^(-1/k)`. One multi-source
+/// [`ShortestPath::get_from`] per level gives every vertex its nearest
+/// witness `p_i(v)` in `A_i`, and the bunch of `v` holds each `w` in
+/// `A_i \ A_{i+1}` closer to `v` than `A_{i+1}` is. That takes
+/// `O(k n^(1 + 1/k))` space in expectation, and a query hops between the two
+/// endpoints' witnesses for at most `k` levels.
+///
+/// Distances are those of the undirected graph: every edge may be used in
+/// both directions. Estimates never undershoot and overshoot by at most the
+/// factor [`Self::stretch`].
+#[derive(Debug, Clone)]
+pub struct DistanceOracle {
+    k: usize,
+    // witnesses[i][v] = (p_i(v), d(A_i, v)), None if A_i is out of reach
+    witnesses: Vec<Vec<Option<(Vertex, Length)>>>,
+    bunches: Vec<HashMap<Vertex, Length>>,
+}
+
+impl DistanceOracle {
+    const SEED: u64 = 0x5eed;
+
+    /// Builds an oracle with stretch `2k - 1`; `k = 1` stores exact all-pairs
+    /// distances.
+    ///
+    /// # Panics
+    /// If `k` is zero.
+    pub fn new(graph: impl Into<Graph>, k: usize) -> Self {
+        assert!(k >= 1, "the oracle needs at least one level");
+        let graph = graph.into();
+
+        let n = graph.len();
+        let mut undirected = vec![Vec::new(); n];
+        for (u, edges) in graph.iter().enumerate() {
+            for edge in edges {
+                undirected[u].push(edge.clone());
+                undirected[*edge.vertex()].push(Edge::new(u, *edge.length()));
+            }
+        }
+        let graph = Graph::from(undirected);
+
+        let level = sample_levels(n, k);
+        let mut sp = ShortestPath::new(graph.clone());
+        let witnesses: Vec<Vec<_>> = (0..k)
+            .map(|i| {
+                let sample: Vec<_> = (0..n).filter(|&v| level[v] >= i).collect();
+                let distances = sp.get_from(&sample, None);
+                sp.nearest_sources()
+                    .iter()
+                    .zip(distances)
+                    .map(|(w, d)| w.map(|w| (w, d)))
+                    .collect()
+            })
+            .collect();
+
+        // cluster of w in A_i \ A_{i+1}: the vertices closer to w than to
+        // A_{i+1}; w joins the bunch of each of them
+        let mut bunches = vec![HashMap::new(); n];
+        let mut search = Search::default();
+        for (w, &i) in level.iter().enumerate() {
+            let bound = |v: Vertex| {
+                witnesses
+                    .get(i + 1)
+                    .and_then(|level: &Vec<_>| level[v])
+                    .map_or(Length::INFINITY, |(_, d)| d)
+            };
+            if bound(w) <= 0.0 {
+                continue;
+            }
+            search.reset(n, w);
+            search.heap.push(w, 0.0);
+            while let Some(edge) = search.heap.pop() {
+                let (u, dist) = (*edge.vertex(), *edge.length());
+                bunches[u].insert(w, dist);
+                for edge in &graph[u] {
+                    let (v, new_dist) = (*edge.vertex(), dist + edge.length());
+                    if new_dist < bound(v) && search.relax(u, v, new_dist) {
+                        search.heap.push(v, new_dist);
+                    }
+                }
+            }
+        }
+
+        Self {
+            k,
+            witnesses,
+            bunches,
+        }
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Worst-case ratio between an estimate and the true distance, `2k - 1`.
+    pub fn stretch(&self) -> usize {
+        2 * self.k - 1
+    }
+
+    /// Estimate of the distance between `u` and `v`, between the true distance
+    /// and [`Self::stretch`] times it; `INFINITY` if they are not connected.
+    pub fn distance(&self, mut u: Vertex, mut v: Vertex) -> Length {
+        let (mut w, mut to_u) = (u, 0.0);
+        for i in 1..=self.k {
+            if let Some(&to_v) = self.bunches[v].get(&w) {
+                return to_u + to_v;
+            }
+            let Some(&Some((next, d))) = self.witnesses.get(i).map(|level| &level[v]) else {
+                break;
+            };
+            (u, v) = (v, u);
+            (w, to_u) = (next, d);
+        }
+        Length::INFINITY
+    }
+
+    /// Total number of bunch entries, the dominant term of the oracle size.
+    pub fn entries(&self) -> usize {
+        self.bunches.iter().map(HashMap::len).sum()
+    }
+}
+
+/// Highest sampled level of every vertex. Keeps resampling until `A_{k-1}`
+/// is non-empty, otherwise the top level would answer nothing.
+fn sample_levels(n: usize, k: usize) -> Vec<usize> {
+    let p = (n as f64).powf(-1.0 / k as f64);
+    let mut state = DistanceOracle::SEED;
+    loop {
+        let level: Vec<_> = (0..n)
+            .map(|_| (1..k).take_while(|_| uniform(&mut state) < p).count())
+            .collect();
+        if n == 0 || level.contains(&(k - 1)) {
+            return level;
+        }
+    }
+}
+
+/// SplitMix64 step mapped to `[0, 1)`.
+fn uniform(state: &mut u64) -> f64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (z ^ (z >> 31)) as f64 / 2f64.powi(64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_level_is_exact() {
+        let graph = vec![
+            vec![Edge::new(1, 2.0)],
+            vec![Edge::new(2, 3.0)],
+            Vec::new(),
+            Vec::new(),
+        ];
+        let oracle = DistanceOracle::new(graph, 1);
+        assert_eq!(oracle.stretch(), 1);
+        assert_eq!(oracle.distance(0, 2), 5.0);
+        assert_eq!(oracle.distance(2, 0), 5.0);
+        assert_eq!(oracle.distance(1, 1), 0.0);
+        assert_eq!(oracle.distance(0, 3), Length::INFINITY);
+    }
+
+    #[test]
+    fn test_components_stay_apart() {
+        // two paths of 50 vertices each
+        let mut graph = vec![Vec::new(); 100];
+        for u in (0..99).filter(|&u| u != 49) {
+            graph[u].push(Edge::new(u + 1, 1.0));
+        }
+        for k in 1..4 {
+            let oracle = DistanceOracle::new(graph.clone(), k);
+            assert_eq!(oracle.distance(10, 60), Length::INFINITY, "k = {k}");
+            assert!(oracle.distance(10, 40) >= 30.0, "k = {k}");
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{
+    ShortestPath,
+    error::GraphError,
+    filter::Filter,
+    models::{Edge, Graph, Length, Vertex, Weight},
+};
+
+/// Which lengths of a [`MultiGraph`] a query uses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Profile<'a> {
+    /// A single weight column, e.g. travel time.
+    Column(usize),
+    /// `sum(coefficients[i] * column[i])`, e.g. time plus a toll surcharge.
+    /// Coefficients must be finite and non-negative, one per column.
+    Linear(&'a [Length]),
+}
+
+/// Edge lengths the relaxation loops of [`ShortestPath`] read: those stored in
+/// the graph, `1.0` for every edge, those of a copy with the same topology
+/// (e.g. rounded), or a profile of [`MultiGraph`] evaluated per edge.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Lengths<'a> {
+    Graph,
+    Unit,
+    Rounded(&'a Graph),
+    Profile {
+        // edge j of u is column entry offsets[u] + j
+        offsets: &'a [usize],
+        columns: &'a [Vec<Length>],
+        profile: Profile<'a>,
+    },
+}
+
+impl Lengths<'_> {
+    /// Length of `edge`, the `j`-th out-edge of `u`.
+    #[inline]
+    pub(crate) fn get(&self, u: Vertex, j: usize, edge: &Edge) -> Length {
+        match *self {
+            Self::Graph => *edge.length(),
+            Self::Unit => 1.0,
+            Self::Rounded(graph) => *graph[u][j].length(),
+            Self::Profile {
+                offsets,
+                columns,
+                profile,
+            } => {
+                let i = offsets[u] + j;
+                match profile {
+                    Profile::Column(c) => columns[c][i],
+                    Profile::Linear(coefficients) => coefficients
+                        .iter()
+                        .zip(columns)
+                        .map(|(c, column)| c * column[i])
+                        .sum(),
+                }
+            }
+        }
+    }
+}
+
+/// Graph with several weights per edge, stored column-wise.
+///
+/// The topology is kept once and every weight profile is a separate column
+/// indexed by edge, so a new profile costs one `f32` per edge. A single
+/// solver serves every profile: [`Self::get`] reads the lengths of the
+/// requested column or linear combination while it relaxes edges, without
+/// building a graph per profile.
+#[derive(Debug, Default)]
+pub struct MultiGraph {
+    // edges of u are columns[_][offsets[u]..offsets[u + 1]]
+    offsets: Vec<usize>,
+    columns: Vec<Vec<Length>>,
+    // solver over the topology; the lengths of its graph are unused
+    sp: ShortestPath,
+}
+
+impl MultiGraph {
+    /// Builds a graph from adjacency lists and one weight column per profile,
+    /// each listing the edge weights in adjacency order (all edges of vertex
+    /// 0, then of vertex 1, ...). Rejects NaN, infinite and negative weights.
+    ///
+    /// # Panics
+    /// If a column does not have exactly one weight per edge.
+    pub fn new(adjacency: Vec<Vec<Vertex>>, columns: Vec<Vec<Length>>) -> Result<Self, GraphError> {
+        let mut offsets = Vec::with_capacity(adjacency.len() + 1);
+        offsets.push(0);
+        let topology: Vec<Vec<Edge>> = adjacency
+            .into_iter()
+            .map(|edges| {
+                offsets.push(offsets[offsets.len() - 1] + edges.len());
+                edges.into_iter().map(|v| Edge::new(v, 0.0)).collect()
+            })
+            .collect();
+        let m = offsets[offsets.len() - 1];
+        for (i, column) in columns.iter().enumerate() {
+            assert_eq!(column.len(), m, "column {i} has the wrong length");
+        }
+
+        let graph = Self {
+            offsets,
+            columns,
+            sp: ShortestPath::new(topology),
+        };
+        for column in &graph.columns {
+            for (u, v, i) in graph.edges() {
+                Weight::new(column[i]).map_err(|error| GraphError {
+                    from: u,
+                    to: v,
+                    error,
+                })?;
+            }
+        }
+        Ok(graph)
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn profiles(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Weights of one profile, in edge order.
+    pub fn column(&self, profile: usize) -> &[Length] {
+        &self.columns[profile]
+    }
+
+    /// Distances from `s` under the lengths of `profile`.
+    ///
+    /// Edges whose combined length overflows to infinity are never used.
+    ///
+    /// # Panics
+    /// See [`Self::graph`].
+    pub fn get(&mut self, s: Vertex, profile: Profile) -> Vec<Length> {
+        self.check(profile);
+        let lengths = Lengths::Profile {
+            offsets: &self.offsets,
+            columns: &self.columns,
+            profile,
+        };
+        self.sp.run(&[s], &Filter::default(), lengths)
+    }
+
+    /// Shortest path from the source of the last [`Self::get`] to `target`,
+    /// under that query's profile.
+    ///
+    /// Returns `None` if `target` is unreachable.
+    pub fn path(&self, target: Vertex) -> Option<Vec<Vertex>> {
+        self.sp.path(target)
+    }
+
+    /// The graph with the lengths of `profile`, e.g. for the algorithms that
+    /// take a [`Graph`] directly. Copies every edge.
+    ///
+    /// # Panics
+    /// If the profile does not exist, or a linear combination has the wrong
+    /// number of coefficients or a negative or non-finite one.
+    pub fn graph(&self, profile: Profile) -> Graph {
+        self.check(profile);
+        let lengths = Lengths::Profile {
+            offsets: &self.offsets,
+            columns: &self.columns,
+            profile,
+        };
+        let topology = self.sp.graph();
+        Graph::from(
+            topology
+                .iter()
+                .enumerate()
+                .map(|(u, edges)| {
+                    edges
+                        .iter()
+                        .enumerate()
+                        .map(|(j, edge)| Edge::new(*edge.vertex(), lengths.get(u, j, edge)))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn check(&self, profile: Profile) {
+        match profile {
+            Profile::Column(i) => assert!(i < self.profiles(), "no profile {i}"),
+            Profile::Linear(coefficients) => {
+                assert_eq!(
+                    coefficients.len(),
+                    self.profiles(),
+                    "one coefficient per profile"
+                );
+                assert!(
+                    coefficients.iter().all(|c| c.is_finite() && *c >= 0.0),
+                    "coefficients must be finite and non-negative"
+                );
+            }
+        }
+    }
+
+    /// `(u, v, edge index)` of every edge.
+    fn edges(&self) -> impl Iterator<Item = (Vertex, Vertex, usize)> + '_ {
+        self.sp
+            .graph()
+            .iter()
+            .enumerate()
+            .flat_map(move |(u, edges)| {
+                edges
+                    .iter()
+                    .enumerate()
+                    .map(move |(j, edge)| (u, *edge.vertex(), self.offsets[u] + j))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WeightError;
+
+    // 0 -> 1 -> 2 is fast but tolled, 0 -> 2 is slow and free
+    fn roads() -> MultiGraph {
+        let adjacency = vec![vec![1, 2], vec![2], vec![]];
+        let time = vec![1.0, 5.0, 1.0];
+        let toll = vec![4.0, 0.0, 4.0];
+        MultiGraph::new(adjacency, vec![time, toll]).unwrap()
+    }
+
+    #[test]
+    fn test_profiles_change_routes() {
+        let mut roads = roads();
+        assert_eq!(roads.get(0, Profile::Column(0)), vec![0.0, 1.0, 2.0]);
+        assert_eq!(roads.path(2), Some(vec![0, 1, 2]));
+        assert_eq!(roads.get(0, Profile::Column(1)), vec![0.0, 4.0, 0.0]);
+
+        assert_eq!(
+            roads.get(0, Profile::Linear(&[1.0, 0.5])),
+            vec![0.0, 3.0, 5.0]
+        );
+        assert_eq!(roads.path(2), Some(vec![0, 2]));
+    }
+
+    #[test]
+    fn test_graph_matches_view() {
+        let mut roads = roads();
+        for profile in [Profile::Column(0), Profile::Linear(&[2.0, 0.25])] {
+            let mut sp = ShortestPath::new(roads.graph(profile));
+            assert_eq!(roads.get(0, profile), sp.get(0), "{profile:?}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_column_is_rejected() {
+        let err = MultiGraph::new(vec![vec![1], vec![]], vec![vec![1.0], vec![-1.0]]).unwrap_err();
+        assert_eq!(
+            (err.from, err.to, err.error),
+            (0, 1, WeightError::Negative(-1.0))
+        );
+    }
+}
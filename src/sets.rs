@@ -0,0 +1,124 @@
+use crate::models::Vertex;
+
+/// Dense vertex set with O(1) membership tests and O(1) clearing.
+///
+/// Membership is an epoch stamp per vertex: clearing bumps the epoch instead of
+/// touching the stamps, and the member list keeps its capacity between uses.
+#[derive(Debug, Default, Clone)]
+pub struct VertexSet {
+    stamp: Vec<u32>,
+    epoch: u32,
+    items: Vec<Vertex>,
+}
+
+impl VertexSet {
+    pub fn new(n: usize) -> Self {
+        Self {
+            stamp: vec![0; n],
+            epoch: 1,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.epoch = self.epoch.wrapping_add(1);
+        if self.epoch == 0 {
+            self.stamp.fill(0);
+            self.epoch = 1;
+        }
+    }
+
+    /// Returns whether `v` was newly inserted.
+    pub fn insert(&mut self, v: Vertex) -> bool {
+        if self.stamp[v] == self.epoch {
+            return false;
+        }
+        self.stamp[v] = self.epoch;
+        self.items.push(v);
+        true
+    }
+
+    pub fn contains(&self, v: Vertex) -> bool {
+        self.stamp[v] == self.epoch
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Members in insertion order.
+    pub fn as_slice(&self) -> &[Vertex] {
+        &self.items
+    }
+}
+
+/// Fixed-size bitset over vertices, one bit per vertex.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new(n: usize) -> Self {
+        Self {
+            words: vec![0; n.div_ceil(64)],
+        }
+    }
+
+    pub fn insert(&mut self, v: Vertex) {
+        self.words[v / 64] |= 1 << (v % 64);
+    }
+
+    pub fn remove(&mut self, v: Vertex) {
+        self.words[v / 64] &= !(1 << (v % 64));
+    }
+
+    /// Vertices beyond the size of the set are never members.
+    pub fn contains(&self, v: Vertex) -> bool {
+        self.words
+            .get(v / 64)
+            .is_some_and(|word| word & (1 << (v % 64)) != 0)
+    }
+}
+
+impl FromIterator<Vertex> for BitSet {
+    fn from_iter<I: IntoIterator<Item = Vertex>>(iter: I) -> Self {
+        let mut set = Self::default();
+        for v in iter {
+            if v / 64 >= set.words.len() {
+                set.words.resize(v / 64 + 1, 0);
+            }
+            set.insert(v);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vertex_set_clear() {
+        let mut set = VertexSet::new(4);
+        assert!(set.insert(2));
+        assert!(!set.insert(2));
+        assert!(set.insert(0));
+        assert_eq!(set.as_slice(), &[2, 0]);
+
+        set.clear();
+        assert_eq!(set.len(), 0);
+        assert!(set.insert(2));
+        assert_eq!(set.as_slice(), &[2]);
+    }
+
+    #[test]
+    fn test_bitset() {
+        let mut set: BitSet = [3, 64, 130].into_iter().collect();
+        assert!(set.contains(64) && set.contains(130) && !set.contains(65));
+        assert!(!set.contains(1000));
+        set.remove(64);
+        assert!(!set.contains(64) && set.contains(3));
+    }
+}
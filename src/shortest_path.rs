@@ -1,241 +1,841 @@
-use std::collections::HashSet;
+mod astar;
+mod bidirectional;
+mod dag;
+pub(crate) mod search;
+mod voronoi;
+
+pub use astar::Route;
+pub use bidirectional::Meeting;
+pub use dag::PathDag;
+pub use voronoi::Voronoi;
+
+use std::collections::VecDeque;
 
 use crate::{
-    heaps::{BlockHeap, Entry, Heap},
-    models::{Graph, Length, Vertex},
+    filter::Filter,
+    heaps::{BlockHeap, Queue, QueueKind},
+    models::{Graph, Length, Vertex, Weight},
+    profiles::Lengths,
+    sets::VertexSet,
 };
 
-struct Pivots {
-    p: Vec<Vertex>,
-    w: Vec<Vertex>,
-}
-impl Pivots {
-    pub fn new(p: Vec<Vertex>, w: Vec<Vertex>) -> Self {
-        Self { p, w }
-    }
-}
-
 #[derive(Debug, Default)]
 pub struct ShortestPath {
     //G_
     graph: Graph,
+    // lengths rounded by `with_approximation`, read by `get` and `get_from` only
+    rounded: Option<Graph>,
     t: usize,
     k: usize,
+    l: usize,
     dhat: Vec<Length>,
+    // shortest-path tree of the last query; only strict improvements re-parent,
+    // which keeps it acyclic even with zero-weight cycles
+    parent: Vec<Option<Vertex>>,
+    // source each vertex was reached from, copied along with every re-parenting
+    source: Vec<Option<Vertex>>,
+    // whether the last run ran under a filter, whose edges `path_dag` cannot
+    // rebuild
+    filtered: bool,
+    // solver over the transposed graph, built on the first `get_to`
+    reverse: Option<Box<ShortestPath>>,
+    // scratch of the point-to-point queries; `bidirectional` uses both, `astar`
+    // and `nearest` the first
+    searches: [search::Search; 2],
+    queue_kind: QueueKind,
+    heap: Queue,
+    // `with_unit_weights` override, else whether the graph has unit weights,
+    // detected on the first query and reset by builders that change lengths
+    unit_weights: Option<bool>,
+    unit_detected: Option<bool>,
+    // cost of passing through each vertex; empty if there are none
+    vertex_weights: Vec<Length>,
+    // U of the active call at each recursion level, level 0 being the base case;
+    // a call leaves its result there and returns only the bound
+    u_sets: Vec<VertexSet>,
 
     // find pivots attributes
     prev: Vec<Option<Vertex>>,
     tree_size: Vec<Option<usize>>,
     f: Vec<Vec<Vertex>>,
+    // W of the active call at each level, read again once the level is done
+    w_sets: Vec<VertexSet>,
+    pivots: Vec<Vertex>,
+    wi: VertexSet,
+    wp: Vec<Vertex>,
+    tree_stack: Vec<(Vertex, usize)>,
+    // vertices improved in the previous `get_hop_limited` round, with the
+    // distance they had at its end
+    frontier: Vec<(Vertex, Length)>,
 }
 
 impl ShortestPath {
     pub fn new(graph: impl Into<Graph>) -> Self {
+        Self::with_graph(graph.into())
+    }
+
+    /// Allocates every buffer the queries reuse, sized to `graph`.
+    fn with_graph(graph: Graph) -> Self {
+        let n = graph.len();
+        let t = (n as f64).log2().powf(2.0 / 3.0).floor();
+        let k = (n as f64).log2().powf(1.0 / 3.0).ceil() as usize;
+        let l = ((n as f64).log2() / t).ceil() as usize;
         Self {
-            graph: graph.into(),
+            graph,
+            t: t as usize,
+            k,
+            l,
+            dhat: vec![Length::INFINITY; n],
+            parent: vec![None; n],
+            source: vec![None; n],
+            heap: Queue::new(QueueKind::DAry, n, 0.0),
+            u_sets: vec![VertexSet::new(n); l + 1],
+            prev: vec![None; n],
+            tree_size: vec![None; n],
+            f: vec![Vec::new(); n],
+            w_sets: vec![VertexSet::new(n); l + 1],
+            wi: VertexSet::new(n),
             ..Default::default()
         }
     }
+
+    /// Selects the priority queue used by the base case.
+    ///
+    /// # Panics
+    /// If an integer queue is requested for a graph whose edge or vertex
+    /// weights are not all non-negative integers.
+    pub fn with_queue(mut self, kind: QueueKind) -> Self {
+        assert!(
+            kind == QueueKind::DAry
+                || (self.rounded.is_none()
+                    && self.graph.has_integer_weights()
+                    && self.vertex_weights.iter().all(|w| w.fract() == 0.0)),
+            "{kind:?} queue requires non-negative integer weights"
+        );
+        self.queue_kind = kind;
+        self.heap = self.queue();
+        self
+    }
+
+    /// Forces (`true`) or disables (`false`) the breadth-first fast path.
+    ///
+    /// By default it is used whenever every edge has length `1.0`. Forcing it
+    /// makes every query of the solver count each edge as `1.0`, i.e. return
+    /// hop counts, whatever the graph stores. Vertex weights are still
+    /// charged, and then the full algorithm runs instead of the fast path.
+    pub fn with_unit_weights(mut self, unit_weights: bool) -> Self {
+        self.unit_weights = Some(unit_weights);
+        self
+    }
+
+    /// Switches [`Self::get`], [`Self::get_from`] and [`Self::get_to`] to
+    /// (1 + `epsilon`)-approximate distances.
+    ///
+    /// They read a copy of the edge lengths rounded up to powers of
+    /// `1 + epsilon` (see [`Graph::rounded`]), so every reported distance `d'`
+    /// satisfies `d <= d' <= (1 + epsilon) d`, and [`Self::path`] returns a real
+    /// path no longer than `d'`. With few distinct lengths many vertices share
+    /// a key and leave the heaps in the same pull. The point-to-point queries
+    /// keep using the exact lengths.
+    ///
+    /// # Panics
+    /// If `epsilon` is not positive and finite, or an integer queue was
+    /// selected.
+    pub fn with_approximation(mut self, epsilon: f64) -> Self {
+        assert!(
+            epsilon > 0.0 && epsilon.is_finite(),
+            "epsilon must be positive and finite, got {epsilon}"
+        );
+        assert_eq!(
+            self.queue_kind,
+            QueueKind::DAry,
+            "rounded weights are not integers"
+        );
+        self.rounded = Some(self.graph.rounded(epsilon));
+        self.unit_detected = None;
+        self.reverse = None;
+        self
+    }
+
+    /// Charges `weights[v]` whenever a path passes through `v`, like a
+    /// junction delay. The endpoints of a path pay nothing, so this matches
+    /// splitting every vertex into an entry and an exit joined by an edge of
+    /// that weight. Every query of the solver respects it, including
+    /// [`Self::get_to`], [`Self::bidirectional`] and [`Self::astar`].
+    ///
+    /// # Panics
+    /// If there is not one weight per vertex, a weight is NaN, infinite or
+    /// negative, or an integer queue was selected and a weight is fractional.
+    pub fn with_vertex_weights(mut self, weights: Vec<Length>) -> Self {
+        assert_eq!(weights.len(), self.graph.len(), "one weight per vertex");
+        for (v, &weight) in weights.iter().enumerate() {
+            if let Err(err) = Weight::new(weight) {
+                panic!("invalid weight of vertex {v}: {err}");
+            }
+            assert!(
+                self.queue_kind == QueueKind::DAry || weight.fract() == 0.0,
+                "{:?} queue requires integer vertex weights, vertex {v} has {weight}",
+                self.queue_kind
+            );
+        }
+        self.vertex_weights = weights;
+        self.heap = self.queue();
+        self.reverse = None;
+        self
+    }
+
     pub fn get(&mut self, s: Vertex) -> Vec<Length> {
+        self.get_from(&[s], None)
+    }
+
+    /// Distances from `s` over paths of at most `hops` edges, e.g. under a
+    /// TTL limit.
+    ///
+    /// Runs `hops` synchronous Bellman-Ford rounds, the relaxation
+    /// `find_pivots` performs for its `k` steps, but each round only
+    /// reads distances from the previous one, so no path gains an extra edge
+    /// within a round. Vertex weights and `filter` apply; the tree of the last
+    /// [`Self::get`] is left as it was.
+    pub fn get_hop_limited(
+        &mut self,
+        s: Vertex,
+        hops: usize,
+        filter: Option<&Filter>,
+    ) -> Vec<Length> {
+        let n = self.graph.len();
+        let filter = filter.copied().unwrap_or_default();
+        if filter.blocks(s) {
+            return vec![Length::INFINITY; n];
+        }
+        let lengths = self.lengths(None);
+        let search = &mut self.searches[0];
+        search.reset(n, s);
+        self.frontier.clear();
+        self.frontier.push((s, 0.0));
+
+        for _ in 0..hops {
+            if self.frontier.is_empty() {
+                break;
+            }
+            self.wi.clear();
+            for &(u, du) in &self.frontier {
+                let toll = pass_cost(&self.vertex_weights, &search.parent, u);
+                for (v, w) in arcs(&self.graph, u, &filter, lengths) {
+                    if search.relax(u, v, du + toll + w) {
+                        self.wi.insert(v);
+                    }
+                }
+            }
+            self.frontier.clear();
+            let improved = self.wi.as_slice().iter();
+            self.frontier.extend(improved.map(|&v| (v, search.dist[v])));
+        }
+        search.dist.clone()
+    }
+
+    /// The first `k` vertices matching `predicate`, closest first, with their
+    /// distances from `source`.
+    ///
+    /// Like the base case, this is Dijkstra cut off after a fixed number of
+    /// settled vertices: it stops as soon as the `k`-th match is settled, so
+    /// the cost depends on how far the matches are rather than on the graph.
+    /// The source itself is a candidate unless `filter` blocks it.
+    pub fn nearest(
+        &mut self,
+        source: Vertex,
+        k: usize,
+        predicate: impl Fn(Vertex) -> bool,
+        filter: Option<&Filter>,
+    ) -> Vec<(Vertex, Length)> {
         let n = self.graph.len();
+        let filter = filter.copied().unwrap_or_default();
+        if filter.blocks(source) {
+            return Vec::new();
+        }
+        let lengths = self.lengths(None);
+        let search = &mut self.searches[0];
+        search.reset(n, source);
+        search.heap.push(source, 0.0);
+
+        let mut found = Vec::with_capacity(k);
+        while found.len() < k {
+            let Some(edge) = search.heap.pop() else {
+                break;
+            };
+            let (u, du) = (*edge.vertex(), *edge.length());
+            if predicate(u) {
+                found.push((u, du));
+            }
 
-        // NOTE: initialize to avoid error out of bounds
-        self.dhat = vec![Length::INFINITY; n];
-        self.dhat[s] = 0.0;
-        self.prev = vec![None; n];
-        self.tree_size = vec![None; n];
-        self.f = vec![Vec::new(); n];
+            let toll = pass_cost(&self.vertex_weights, &search.parent, u);
+            for (v, w) in arcs(&self.graph, u, &filter, lengths) {
+                let new_dist = du + toll + w;
+                if search.relax(u, v, new_dist) {
+                    search.heap.push(v, new_dist);
+                }
+            }
+        }
+        found
+    }
 
-        let n = n as f64;
-        let t = (n.log2().powf(2.0 / 3.0)).floor();
-        self.k = (n.log2().powf(1.0 / 3.0)).ceil() as usize;
-        self.t = t as usize;
-        let l = (n.log2() / t).ceil() as usize;
+    /// Multi-source query: the distance from the nearest of `sources` to every
+    /// vertex. [`Self::path`] then follows the forest rooted at the sources,
+    /// and [`Self::nearest_sources`] names the root of every vertex.
+    ///
+    /// With a `filter`, only the vertices and edges it allows are used and a
+    /// blocked source reaches nothing; the graph itself is left untouched.
+    pub fn get_from(&mut self, sources: &[Vertex], filter: Option<&Filter>) -> Vec<Length> {
+        // NOTE: taken out so `run` can borrow the solver mutably
+        let rounded = self.rounded.take();
+        let lengths = self.lengths(rounded.as_ref());
+        let distances = self.run(sources, &filter.copied().unwrap_or_default(), lengths);
+        self.rounded = rounded;
+        self.filtered = filter.is_some();
+        distances
+    }
 
-        let mut source_set = Vec::new();
-        source_set.push(s);
-        self.bmssp(l, Length::INFINITY, &source_set);
+    /// Distance from the nearest of `sources` to every vertex, over the edges
+    /// `filter` allows and with edge lengths read through `lengths`.
+    pub(crate) fn run(
+        &mut self,
+        sources: &[Vertex],
+        filter: &Filter,
+        lengths: Lengths,
+    ) -> Vec<Length> {
+        self.dhat.fill(Length::INFINITY);
+        self.parent.fill(None);
+        self.source.fill(None);
+        self.filtered = false;
+        let mut source_set = Vec::with_capacity(sources.len());
+        for &s in sources {
+            if self.dhat[s] != 0.0 && !filter.blocks(s) {
+                self.dhat[s] = 0.0;
+                self.source[s] = Some(s);
+                source_set.push(s);
+            }
+        }
+        if source_set.is_empty() {
+            return self.dhat.clone();
+        }
+
+        let unit_weights = match (self.unit_weights, lengths) {
+            (_, Lengths::Unit) => true,
+            (Some(false), _) | (_, Lengths::Profile { .. }) => false,
+            (_, Lengths::Graph) => *self
+                .unit_detected
+                .get_or_insert_with(|| self.graph.has_unit_weights()),
+            (_, Lengths::Rounded(rounded)) => *self
+                .unit_detected
+                .get_or_insert_with(|| rounded.has_unit_weights()),
+        };
+        if unit_weights && self.vertex_weights.is_empty() {
+            self.bfs(&source_set, filter);
+            return self.dhat.clone();
+        }
+
+        // NOTE: `bmssp` recurses once per level, so the stack depth is bounded by
+        // l = ceil(log n / t) frames regardless of the shape of the graph
+        self.bmssp(self.l, Length::INFINITY, &source_set, filter, lengths);
 
         self.dhat.clone()
     }
 
-    fn bmssp(&mut self, l: usize, b: Length, s: &[Vertex]) -> Entry {
+    /// Distances from every vertex to `target`.
+    ///
+    /// Runs on the transposed graph, which is built and cached on first use
+    /// with the same queue and unit-weight settings as this solver. `filter`
+    /// sees the edges in their forward direction.
+    pub fn get_to(&mut self, target: Vertex, filter: Option<&Filter>) -> Vec<Length> {
+        let filter = filter.map(|filter| filter.transposed());
+        self.reverse().get_from(&[target], filter.as_ref())
+    }
+
+    /// Shortest path from the (nearest) source of the last [`Self::get`] to `target`.
+    ///
+    /// Returns `None` if `target` is unreachable.
+    pub fn path(&self, target: Vertex) -> Option<Vec<Vertex>> {
+        let mut path = self.tree_path(target)?;
+        path.reverse();
+        Some(path)
+    }
+
+    /// Shortest path from `source` to the target of the last [`Self::get_to`],
+    /// in forward edge order.
+    ///
+    /// Returns `None` if the target is unreachable from `source` or
+    /// [`Self::get_to`] was never called.
+    pub fn path_from(&self, source: Vertex) -> Option<Vec<Vertex>> {
+        // NOTE: in the reverse tree the parent of v is its successor towards the target
+        self.reverse.as_ref()?.tree_path(source)
+    }
+
+    /// The source of the last [`Self::get_from`] that each vertex is closest
+    /// to, `None` for unreachable vertices.
+    ///
+    /// Each source labels itself and every relaxation that re-parents a vertex
+    /// hands it the label of its new parent, so no extra pass is needed.
+    pub fn nearest_sources(&self) -> &[Option<Vertex>] {
+        &self.source
+    }
+
+    pub(crate) fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    /// Parents in the shortest-path tree of the last [`Self::get`].
+    pub(crate) fn parents(&self) -> &[Option<Vertex>] {
+        &self.parent
+    }
+
+    fn reverse(&mut self) -> &mut Self {
+        self.reverse.get_or_insert_with(|| {
+            let mut reverse = Self::with_graph(self.graph.reversed());
+            reverse.queue_kind = self.queue_kind;
+            reverse.unit_weights = self.unit_weights;
+            reverse.unit_detected = self.unit_detected;
+            reverse.rounded = self.rounded.as_ref().map(Graph::reversed);
+            reverse.vertex_weights = self.vertex_weights.clone();
+            reverse.heap = reverse.queue();
+            Box::new(reverse)
+        })
+    }
+
+    /// Lengths the queries read: `1.0` per edge if unit weights are forced,
+    /// else `rounded` if given, else those stored in the graph.
+    fn lengths<'a>(&self, rounded: Option<&'a Graph>) -> Lengths<'a> {
+        match rounded {
+            _ if self.unit_weights == Some(true) => Lengths::Unit,
+            Some(rounded) => Lengths::Rounded(rounded),
+            None => Lengths::Graph,
+        }
+    }
+
+    /// A base-case queue of the selected kind; a key grows by at most one edge
+    /// and one vertex weight per relaxation.
+    fn queue(&self) -> Queue {
+        let max_vertex_weight = self.vertex_weights.iter().copied().fold(0.0, Length::max);
+        Queue::new(
+            self.queue_kind,
+            self.graph.len(),
+            self.graph.max_weight() + max_vertex_weight,
+        )
+    }
+
+    /// Tree path from `v` up to the source, i.e. in reverse query order.
+    fn tree_path(&self, mut v: Vertex) -> Option<Vec<Vertex>> {
+        if !self.dhat.get(v)?.is_finite() {
+            return None;
+        }
+        let mut path = vec![v];
+        while let Some(u) = self.parent[v] {
+            path.push(u);
+            v = u;
+        }
+        Some(path)
+    }
+
+    fn bfs(&mut self, sources: &[Vertex], filter: &Filter) {
+        let mut que = VecDeque::from(sources.to_vec());
+        while let Some(u) = que.pop_front() {
+            for edge in &self.graph[u] {
+                let v = *edge.vertex();
+                if self.dhat[v] == Length::INFINITY && filter.allows(u, edge) {
+                    self.dhat[v] = self.dhat[u] + 1.0;
+                    self.parent[v] = Some(u);
+                    self.source[v] = self.source[u];
+                    que.push_back(v);
+                }
+            }
+        }
+    }
+
+    /// Leaves the settled set in `u_sets[l]` and returns its bound.
+    fn bmssp(
+        &mut self,
+        l: usize,
+        b: Length,
+        s: &[Vertex],
+        filter: &Filter,
+        lengths: Lengths,
+    ) -> Length {
         if l == 0 {
-            return self.base_case(b, s);
+            return self.base_case(b, s, filter, lengths);
         }
 
-        let pivots = self.find_pivots(b, s);
+        self.find_pivots(l, b, s, filter, lengths);
 
         let m = 2_usize.pow(((l - 1) * self.t) as u32);
         let mut d = BlockHeap::new(m, b);
         let mut bd = Length::INFINITY;
 
-        for &u in &pivots.p {
+        for &u in &self.pivots {
             d.insert(u, self.dhat[u]);
             bd = bd.min(self.dhat[u]);
         }
 
-        let mut u_set = HashSet::new();
+        // NOTE: taken out so the recursive calls can borrow the solver
+        let mut u_set = std::mem::take(&mut self.u_sets[l]);
+        u_set.clear();
 
         while u_set.len() < self.k * 2_usize.pow((l * self.t) as u32) && !d.is_empty() {
             let entry = d.pull();
-            let b_entry = self.bmssp(l - 1, entry.b(), entry.u_set());
+            let b_next = self.bmssp(l - 1, entry.b(), entry.u_set(), filter, lengths);
+            let settled = std::mem::take(&mut self.u_sets[l - 1]);
 
-            for &u in b_entry.u_set() {
+            for &u in settled.as_slice() {
                 u_set.insert(u);
             }
 
             let mut k_vec = Vec::new();
-            for &u in b_entry.u_set() {
-                for edge in &self.graph[u] {
-                    let v = *edge.vertex();
-                    let w = *edge.length();
-
+            for &u in settled.as_slice() {
+                let toll = pass_cost(&self.vertex_weights, &self.parent, u);
+                for (v, w) in arcs(&self.graph, u, filter, lengths) {
+                    let w = w + toll;
                     if self.dhat[v] >= self.dhat[u] + w {
                         let new_dist = self.dhat[u] + w;
+                        if self.dhat[v] > new_dist {
+                            self.parent[v] = Some(u);
+                            self.source[v] = self.source[u];
+                        }
                         self.dhat[v] = new_dist;
                         if entry.b() <= new_dist && new_dist < b {
                             d.insert(v, new_dist);
-                        } else if b_entry.b() <= new_dist && new_dist < entry.b() {
+                        } else if b_next <= new_dist && new_dist < entry.b() {
                             k_vec.push((v, new_dist));
                         }
                     }
                 }
             }
+            self.u_sets[l - 1] = settled;
 
             for &u in entry.u_set() {
-                if b_entry.b() <= self.dhat[u] && self.dhat[u] < entry.b() {
+                if b_next <= self.dhat[u] && self.dhat[u] < entry.b() {
                     k_vec.push((u, self.dhat[u]));
                 }
             }
 
             d.batch_prepend(&k_vec);
-            bd = b_entry.b();
+            bd = b_next;
         }
 
         bd = bd.min(b);
-        for &u in &pivots.w {
+        for &u in self.w_sets[l].as_slice() {
             if self.dhat[u] < bd {
                 u_set.insert(u);
             }
         }
 
-        Entry::new(bd, u_set.into_iter().collect())
+        self.u_sets[l] = u_set;
+        bd
     }
 
-    fn base_case(&mut self, b: Length, s: &[Vertex]) -> Entry {
-        assert_eq!(s.len(), 1);
+    fn base_case(&mut self, b: Length, s: &[Vertex], filter: &Filter, lengths: Lengths) -> Length {
+        let u0 = &mut self.u_sets[0];
+        u0.clear();
 
-        let x = s[0];
-        let mut u0 = HashSet::new();
-        u0.extend(s.iter());
-
-        let mut h = Heap::new();
-        h.push(x, self.dhat[x]);
+        let h = &mut self.heap;
+        for &x in s {
+            h.push(x, self.dhat[x]);
+        }
 
-        while let Some(edge) = h.pop()
-            && u0.len() < self.k + 1
-        {
+        // NOTE: sources may share a distance when the pull had to break ties, so
+        // stop only once more than k vertices are settled and the next key is
+        // strictly larger; that key is then a valid bound for everything settled.
+        let mut bd = b;
+        let mut last = Length::MIN;
+        while let Some(edge) = h.pop() {
             let u = *edge.vertex();
+            if u0.len() > self.k && *edge.length() > last {
+                bd = *edge.length();
+                break;
+            }
 
             u0.insert(u);
-
-            for graph_edge in &self.graph[u] {
-                let v = *graph_edge.vertex();
-                let w = *graph_edge.length();
-
-                if self.dhat[v] >= self.dhat[u] + w && self.dhat[u] + w < b {
+            last = *edge.length();
+
+            let toll = pass_cost(&self.vertex_weights, &self.parent, u);
+            for (v, w) in arcs(&self.graph, u, filter, lengths) {
+                let w = w + toll;
+                // NOTE: settled vertices are never pushed again, so zero-weight
+                // cycles cannot keep the loop alive
+                if self.dhat[v] >= self.dhat[u] + w && self.dhat[u] + w < b && !u0.contains(v) {
+                    if self.dhat[v] > self.dhat[u] + w {
+                        self.parent[v] = Some(u);
+                        self.source[v] = self.source[u];
+                    }
                     self.dhat[v] = self.dhat[u] + w;
                     h.push(v, self.dhat[v]);
                 }
             }
         }
+        h.clear();
 
-        if u0.len() <= self.k {
-            Entry::new(b, u0.into_iter().collect())
-        } else {
-            let mut bd = Length::MIN;
-            for &u in &u0 {
-                bd = bd.max(self.dhat[u]);
-            }
-
-            //TODO: use a filter map an so on
-            let mut u_vec = Vec::new();
-            for &u in &u0 {
-                if self.dhat[u] < bd {
-                    u_vec.push(u);
-                }
-            }
-            Entry::new(bd, u_vec)
-        }
+        bd
     }
 
-    fn find_pivots(&mut self, b: Length, s: &[Vertex]) -> Pivots {
-        let mut w = HashSet::new();
-        let mut wp = HashSet::new();
-
-        w.extend(s.iter().copied());
-        wp.extend(s.iter().copied());
-
-        for &v in &w {
+    /// Fills `w_sets[l]` with W and `pivots` with P.
+    fn find_pivots(
+        &mut self,
+        l: usize,
+        b: Length,
+        s: &[Vertex],
+        filter: &Filter,
+        lengths: Lengths,
+    ) {
+        let w = &mut self.w_sets[l];
+        w.clear();
+        self.wp.clear();
+        for &v in s {
+            w.insert(v);
+            self.wp.push(v);
             self.prev[v] = None;
         }
 
+        self.pivots.clear();
         for _ in 0..self.k {
-            let mut wi = HashSet::new();
-            for &u in &wp {
-                for graph_edge in &self.graph[u] {
-                    let v = *graph_edge.vertex();
-                    let w = *graph_edge.length();
-
-                    if self.dhat[v] >= self.dhat[u] + w && self.dhat[u] + w < b{
-                        self.dhat[v] = self.dhat[u] + w;
-                        self.prev[v] = Some(u);
-                        wi.insert(v);
+            self.wi.clear();
+            for &u in &self.wp {
+                let toll = pass_cost(&self.vertex_weights, &self.parent, u);
+                for (v, w_uv) in arcs(&self.graph, u, filter, lengths) {
+                    let w_uv = w_uv + toll;
+
+                    if self.dhat[v] >= self.dhat[u] + w_uv {
+                        // NOTE: a tie only re-parents vertices new to W; otherwise
+                        // zero-weight cycles would turn the forest into a cycle
+                        if self.dhat[v] > self.dhat[u] + w_uv {
+                            self.parent[v] = Some(u);
+                            self.source[v] = self.source[u];
+                            self.prev[v] = Some(u);
+                        } else if !w.contains(v) {
+                            self.prev[v] = Some(u);
+                        }
+                        self.dhat[v] = self.dhat[u] + w_uv;
+                        if self.dhat[u] + w_uv < b {
+                            self.wi.insert(v);
+                        }
                     }
                 }
             }
 
-            w.extend(&wi);
+            for &v in self.wi.as_slice() {
+                w.insert(v);
+            }
 
             if w.len() >= self.k * s.len() {
-                return Pivots::new(s.to_vec(), w.into_iter().collect());
+                self.pivots.extend_from_slice(s);
+                return;
             }
-            wp = wi;
+            self.wp.clear();
+            self.wp.extend_from_slice(self.wi.as_slice());
         }
 
-        for &v in &w {
+        for &v in w.as_slice() {
             self.tree_size[v] = None;
             self.f[v].clear();
         }
 
-        for &v in &w {
+        for &v in w.as_slice() {
             if let Some(u) = self.prev[v] {
                 self.f[u].push(v);
             }
         }
 
-        let mut p = Vec::new();
         for &u in s {
-            if find_tree_size(u, &mut self.tree_size, &self.f) >= self.k && self.prev[u].is_none() {
-                p.push(u);
+            let size = find_tree_size(u, &mut self.tree_size, &self.f, &mut self.tree_stack);
+            if size >= self.k && self.prev[u].is_none() {
+                self.pivots.push(u);
             }
         }
+    }
+}
+
+/// Out-edges of `u` that `filter` allows, as `(v, length)` under `lengths`.
+#[inline]
+fn arcs<'a>(
+    graph: &'a Graph,
+    u: Vertex,
+    filter: &'a Filter,
+    lengths: Lengths<'a>,
+) -> impl Iterator<Item = (Vertex, Length)> + 'a {
+    graph[u]
+        .iter()
+        .enumerate()
+        .filter(move |(_, edge)| filter.allows(u, edge))
+        .map(move |(j, edge)| (*edge.vertex(), lengths.get(u, j, edge)))
+}
 
-        Pivots::new(p, w.into_iter().collect())
+/// Cost of passing through `u` on the way to its out-neighbours: its vertex
+/// weight, except at the sources, which have no parent.
+#[inline]
+fn pass_cost(vertex_weights: &[Length], parent: &[Option<Vertex>], u: Vertex) -> Length {
+    match (vertex_weights.get(u), parent[u]) {
+        (Some(&cost), Some(_)) => cost,
+        _ => 0.0,
     }
 }
 
-fn find_tree_size(u: usize, tree_size: &mut [Option<usize>], f: &[Vec<Vertex>]) -> usize {
-    if let Some(v) = tree_size[u] {
-        return v;
+/// Size of the subtree of `root` in the forest `f`, memoised in `tree_size`.
+///
+/// Walks the forest post-order with an explicit stack so that deep `prev`
+/// chains cannot overflow the thread stack.
+fn find_tree_size(
+    root: Vertex,
+    tree_size: &mut [Option<usize>],
+    f: &[Vec<Vertex>],
+    stack: &mut Vec<(Vertex, usize)>,
+) -> usize {
+    stack.clear();
+    stack.push((root, 0));
+
+    while let Some((u, i)) = stack.last_mut() {
+        let u = *u;
+        if tree_size[u].is_some() {
+            stack.pop();
+        } else if let Some(&v) = f[u].get(*i) {
+            *i += 1;
+            if tree_size[v].is_none() {
+                stack.push((v, 0));
+            }
+        } else {
+            let children: usize = f[u].iter().filter_map(|&v| tree_size[v]).sum();
+            tree_size[u] = Some(1 + children);
+            stack.pop();
+        }
+    }
+
+    tree_size[root].unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        heuristic::{Coordinates, Metric},
+        models::Edge,
+    };
+
+    #[test]
+    fn test_approximation_keeps_point_queries_exact() {
+        // 0.75 rounds up to 1.0 with epsilon 0.5
+        let graph = vec![vec![Edge::new(1, 0.75)], vec![Edge::new(2, 0.75)], vec![]];
+        let mut sp = ShortestPath::new(graph);
+        assert_eq!(sp.get(0), vec![0.0, 0.75, 1.5]);
+
+        let mut sp = sp.with_approximation(0.5);
+        assert_eq!(sp.get(0), vec![0.0, 1.0, 2.0]);
+        assert_eq!(sp.get_to(2, None), vec![2.0, 1.0, 0.0]);
+        assert_eq!(sp.bidirectional(0, 2, None).unwrap().length, 1.5);
+        assert_eq!(
+            sp.nearest(0, 3, |_| true, None),
+            [(0, 0.0), (1, 0.75), (2, 1.5)]
+        );
     }
 
-    let mut res = 1;
-    for &v in &f[u] {
-        res += find_tree_size(v, tree_size, f);
+    // 0 -> {1, 2} -> 3 with unit edges; passing 1 or 2 costs 5
+    fn diamond() -> Vec<Vec<Edge>> {
+        vec![
+            vec![Edge::new(1, 1.0), Edge::new(2, 1.0)],
+            vec![Edge::new(3, 1.0)],
+            vec![Edge::new(3, 1.0)],
+            vec![],
+        ]
     }
 
-    tree_size[u] = Some(res);
-    res
+    #[test]
+    fn test_vertex_weights_after_unit_weight_query() {
+        let mut sp = ShortestPath::new(diamond());
+        assert_eq!(sp.get(0)[3], 2.0);
+        let mut sp = sp.with_vertex_weights(vec![0.0, 5.0, 5.0, 0.0]);
+        assert_eq!(sp.get(0)[3], 7.0);
+        assert_eq!(sp.bidirectional(0, 3, None).unwrap().length, 7.0);
+    }
+
+    #[test]
+    fn test_vertex_weights_override_forced_unit_weights() {
+        let mut sp = ShortestPath::new(diamond())
+            .with_unit_weights(true)
+            .with_vertex_weights(vec![0.0, 5.0, 5.0, 0.0]);
+        assert_eq!(sp.get(0), vec![0.0, 1.0, 1.0, 7.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "integer vertex weights")]
+    fn test_integer_queue_rejects_fractional_vertex_weights() {
+        let _ = ShortestPath::new(diamond())
+            .with_queue(QueueKind::Radix)
+            .with_vertex_weights(vec![0.0, 0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "integer weights")]
+    fn test_fractional_vertex_weights_reject_integer_queue() {
+        let _ = ShortestPath::new(diamond())
+            .with_vertex_weights(vec![0.0, 0.5, 0.0, 0.0])
+            .with_queue(QueueKind::Dial);
+    }
+
+    #[test]
+    fn test_forced_unit_weights_apply_to_every_query() {
+        let graph = vec![
+            vec![Edge::new(1, 5.0), Edge::new(2, 20.0)],
+            vec![Edge::new(2, 5.0)],
+            Vec::new(),
+        ];
+        let mut sp = ShortestPath::new(graph).with_unit_weights(true);
+        assert_eq!(sp.get_to(2, None), [1.0, 1.0, 0.0]);
+        assert_eq!(sp.get_hop_limited(0, 2, None), [0.0, 1.0, 1.0]);
+        assert_eq!(sp.nearest(0, 1, |v| v == 2, None), [(2, 1.0)]);
+        assert_eq!(sp.bidirectional(0, 2, None).unwrap().path, [0, 2]);
+
+        let flat = Coordinates::new(vec![(0.0, 0.0); 3], Metric::Euclidean);
+        assert_eq!(sp.astar(0, 2, &flat, None).unwrap().length, 1.0);
+
+        let mut sp = sp.with_vertex_weights(vec![0.0, 0.5, 0.0]);
+        assert_eq!(sp.get(0), [0.0, 1.0, 1.0]);
+        assert_eq!(sp.get_hop_limited(1, 1, None), [Length::INFINITY, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_hop_limits_with_vertex_weights() {
+        // 0 -> 2 directly (10), through 1 (2 + toll 3) or through 3, 4 (3 + tolls 4)
+        let mut graph = vec![Vec::new(); 5];
+        for (u, v, length) in [
+            (0, 2, 10.0),
+            (0, 1, 1.0),
+            (1, 2, 1.0),
+            (0, 3, 1.0),
+            (3, 4, 1.0),
+            (4, 2, 1.0),
+        ] {
+            graph[u].push(Edge::new(v, length));
+        }
+        let mut sp = ShortestPath::new(graph).with_vertex_weights(vec![0.0, 3.0, 0.0, 2.0, 2.0]);
+
+        let limited: Vec<_> = (1..=3)
+            .map(|hops| sp.get_hop_limited(0, hops, None)[2])
+            .collect();
+        assert_eq!(limited, [10.0, 5.0, 5.0]);
+        assert_eq!(sp.get(0)[2], 5.0);
+        assert_eq!(
+            sp.get_hop_limited(3, 2, None),
+            [Length::INFINITY, Length::INFINITY, 4.0, 0.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn test_find_tree_size_deep_chain_small_stack() {
+        let n = 1_000_000;
+        let handle = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || {
+                let mut f: Vec<Vec<Vertex>> = (0..n).map(|u| vec![u + 1]).collect();
+                f[n - 1].clear();
+                let mut tree_size = vec![None; n];
+                let root = find_tree_size(0, &mut tree_size, &f, &mut Vec::new());
+                (root, tree_size[n / 2])
+            })
+            .unwrap();
+
+        assert_eq!(handle.join().unwrap(), (n, Some(n - n / 2)));
+    }
 }
@@ -0,0 +1,102 @@
+use super::{ShortestPath, arcs, pass_cost};
+use crate::{
+    filter::Filter,
+    heuristic::Heuristic,
+    models::{Length, Vertex},
+};
+
+/// Result of a goal-directed point-to-point query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub length: Length,
+    /// Shortest path from the source to the target, in forward order.
+    pub path: Vec<Vertex>,
+    /// Number of vertices taken off the queue, a measure of the search effort.
+    pub settled: usize,
+}
+
+impl ShortestPath {
+    /// A* query from `s` to `t` guided by `heuristic`.
+    ///
+    /// Vertices are keyed by `dist + estimate`; the search stops as soon as `t`
+    /// is settled. Improved vertices are simply pushed again, so an admissible
+    /// but inconsistent heuristic still yields an optimal route. Vertex weights
+    /// and forced unit weights apply as in [`Self::get`], and `filter` as in
+    /// [`Self::get_from`].
+    ///
+    /// Returns `None` if `t` is unreachable from `s`.
+    pub fn astar(
+        &mut self,
+        s: Vertex,
+        t: Vertex,
+        heuristic: &impl Heuristic,
+        filter: Option<&Filter>,
+    ) -> Option<Route> {
+        let n = self.graph.len();
+        let filter = filter.copied().unwrap_or_default();
+        if filter.blocks(s) {
+            return None;
+        }
+        let lengths = self.lengths(None);
+        let search = &mut self.searches[0];
+        search.reset(n, s);
+        search.heap.push(s, heuristic.estimate(s, t));
+
+        let mut settled = 0;
+        while let Some(edge) = search.heap.pop() {
+            let u = *edge.vertex();
+            settled += 1;
+            if u == t {
+                let mut path = search.path(t);
+                path.reverse();
+                return Some(Route {
+                    length: search.dist[t],
+                    path,
+                    settled,
+                });
+            }
+
+            let toll = pass_cost(&self.vertex_weights, &search.parent, u);
+            for (v, w) in arcs(&self.graph, u, &filter, lengths) {
+                let new_dist = search.dist[u] + toll + w;
+                if search.relax(u, v, new_dist) {
+                    search.heap.push(v, new_dist + heuristic.estimate(v, t));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::grid,
+        heuristic::{Coordinates, Metric},
+    };
+
+    #[test]
+    fn test_astar_grid_explores_less() {
+        // 30x30 grid with unit spacing and edges of length 1.5 in all 4 directions
+        let side = 30;
+        let id = |x: usize, y: usize| y * side + x;
+        let graph = grid(side, |_, _| 1.5);
+        let points: Vec<_> = (0..side * side)
+            .map(|v| ((v % side) as f64, (v / side) as f64))
+            .collect();
+        let coords = Coordinates::new(points, Metric::Euclidean).calibrated(&graph.clone().into());
+
+        let mut sp = ShortestPath::new(graph);
+        let (s, t) = (id(2, 2), id(20, 9));
+        let expected = sp.get(s)[t];
+        let route = sp.astar(s, t, &coords, None).unwrap();
+
+        assert_eq!(route.length, expected);
+        assert_eq!((route.path[0], *route.path.last().unwrap()), (s, t));
+        assert_eq!(route.path.len(), 18 + 7 + 1);
+        assert!(route.settled < side * side / 2, "{}", route.settled);
+        assert_eq!(sp.astar(s, s, &coords, None).unwrap().length, 0.0);
+    }
+}
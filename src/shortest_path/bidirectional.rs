@@ -0,0 +1,153 @@
+use super::{ShortestPath, arcs, search::Search};
+use crate::{
+    filter::Filter,
+    models::{Graph, Length, Vertex},
+    profiles::Lengths,
+};
+
+/// Result of [`ShortestPath::bidirectional`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Meeting {
+    /// Vertex where the forward and backward searches met.
+    pub vertex: Vertex,
+    pub length: Length,
+    /// Shortest path from the source to the target, in forward order.
+    pub path: Vec<Vertex>,
+}
+
+impl Search {
+    /// Settles the closest vertex and relaxes its edges, updating the best
+    /// meeting `(length, vertex)` against the opposite search.
+    ///
+    /// Edges are read through `lengths`. Passing through a vertex other than
+    /// the two roots costs its entry of `vertex_weights`, if any; distances
+    /// exclude the cost of their own vertex.
+    pub(crate) fn step(
+        &mut self,
+        graph: &Graph,
+        filter: &Filter,
+        lengths: Lengths,
+        other: &Search,
+        vertex_weights: &[Length],
+        best: &mut (Length, Option<Vertex>),
+    ) {
+        let Some(edge) = self.heap.pop() else {
+            return;
+        };
+        let u = *edge.vertex();
+
+        let roots = [self.root, other.root];
+        let toll = |x: Vertex| match vertex_weights.get(x) {
+            Some(&cost) if !roots.contains(&x) => cost,
+            _ => 0.0,
+        };
+        let departure = self.dist[u] + toll(u);
+        for (v, w) in arcs(graph, u, filter, lengths) {
+            let new_dist = departure + w;
+            if self.relax(u, v, new_dist) {
+                self.heap.push(v, new_dist);
+            }
+            let through = self.dist[v] + other.dist[v] + toll(v);
+            if through < best.0 {
+                *best = (through, Some(v));
+            }
+        }
+    }
+}
+
+impl ShortestPath {
+    /// Point-to-point query from `s` to `t`.
+    ///
+    /// Runs Dijkstra forward from `s` on the graph and backward from `t` on the
+    /// cached transposed graph, always advancing the side whose smallest
+    /// frontier key is lower. It stops once the two smallest keys add up to
+    /// at least the best `s`-`t` length seen, at which point that length is
+    /// optimal. Vertex weights and forced unit weights apply as in
+    /// [`Self::get`], and `filter` as in [`Self::get_from`] on both sides.
+    ///
+    /// Returns `None` if `t` is unreachable from `s`.
+    pub fn bidirectional(
+        &mut self,
+        s: Vertex,
+        t: Vertex,
+        filter: Option<&Filter>,
+    ) -> Option<Meeting> {
+        let n = self.graph.len();
+        let forward_filter = filter.copied().unwrap_or_default();
+        let backward_filter = forward_filter.transposed();
+        if forward_filter.blocks(s) || forward_filter.blocks(t) {
+            return None;
+        }
+        let lengths = self.lengths(None);
+        self.reverse();
+        let backward_graph = &self.reverse.as_ref()?.graph;
+        let [forward, backward] = &mut self.searches;
+        forward.reset(n, s);
+        forward.heap.push(s, 0.0);
+        backward.reset(n, t);
+        backward.heap.push(t, 0.0);
+
+        let mut best = if s == t {
+            (0.0, Some(s))
+        } else {
+            (Length::INFINITY, None)
+        };
+
+        // NOTE: an exhausted side has an infinite top, which also ends the loop
+        loop {
+            let (top_f, top_b) = (forward.top(), backward.top());
+            if top_f + top_b >= best.0 {
+                break;
+            }
+            if top_f <= top_b {
+                forward.step(
+                    &self.graph,
+                    &forward_filter,
+                    lengths,
+                    backward,
+                    &self.vertex_weights,
+                    &mut best,
+                );
+            } else {
+                backward.step(
+                    backward_graph,
+                    &backward_filter,
+                    lengths,
+                    forward,
+                    &self.vertex_weights,
+                    &mut best,
+                );
+            }
+        }
+
+        let (length, vertex) = (best.0, best.1?);
+        let mut path = forward.path(vertex);
+        path.reverse();
+        path.extend(backward.path(vertex).into_iter().skip(1));
+
+        Some(Meeting {
+            vertex,
+            length,
+            path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::sample;
+
+    #[test]
+    fn test_bidirectional_matches_get() {
+        let mut sp = ShortestPath::new(sample());
+        let distances = sp.get(0);
+        for (t, &d) in distances.iter().enumerate() {
+            let meeting = sp.bidirectional(0, t, None).unwrap();
+            assert_eq!(meeting.length, d, "target {t}");
+            assert_eq!(meeting.path, sp.path(t).unwrap(), "target {t}");
+            assert!(meeting.path.contains(&meeting.vertex));
+        }
+        assert_eq!(sp.bidirectional(4, 0, None), None);
+    }
+}
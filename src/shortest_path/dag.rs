@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+
+use super::{ShortestPath, pass_cost};
+use crate::models::{Graph, Length, Vertex};
+
+/// Result of [`ShortestPath::path_dag`].
+#[derive(Debug, Clone)]
+pub struct PathDag {
+    /// Every tight edge `u -> v`, i.e. one on some shortest path, with its
+    /// original length.
+    pub dag: Graph,
+    /// Number of distinct shortest paths from the source to each vertex; 0 if
+    /// unreachable, `INFINITY` behind a tight zero-weight cycle. Kept as `f64`
+    /// because counts grow exponentially with the graph.
+    pub counts: Vec<f64>,
+    /// Reachable vertices in topological order of [`Self::dag`], sources
+    /// first, as needed to accumulate betweenness backwards.
+    pub order: Vec<Vertex>,
+}
+
+impl ShortestPath {
+    /// Builds the DAG of tight edges and the shortest-path counts of the last
+    /// [`Self::get`] (or [`Self::get_from`], where every source counts once
+    /// and paths through another source are not counted).
+    ///
+    /// Float sums are rarely exact, so `u -> v` counts as tight when
+    /// `|d(u) + length - d(v)| <= tolerance * max(d(v), 1)`; a `tolerance` of
+    /// `0.0` requires exact equality. Edge lengths are those the query read,
+    /// i.e. rounded under [`Self::with_approximation`] and `1.0` with forced
+    /// unit weights, and vertex weights are taken into account.
+    ///
+    /// Returns `None` if the last query ran with a filter: it is not kept, so
+    /// the edges it removed could show up as tight.
+    pub fn path_dag(&self, tolerance: Length) -> Option<PathDag> {
+        if self.filtered {
+            return None;
+        }
+        let n = self.graph.len();
+        let is_source = |v: Vertex| self.source[v] == Some(v);
+        let lengths = self.lengths(self.rounded.as_ref());
+        let mut dag = vec![Vec::new(); n];
+        let mut indegree = vec![0; n];
+        for (u, edges) in self.graph.iter().enumerate() {
+            if !self.dhat[u].is_finite() {
+                continue;
+            }
+            let departure = self.dhat[u] + pass_cost(&self.vertex_weights, &self.parent, u);
+            for (j, edge) in edges.iter().enumerate() {
+                let v = *edge.vertex();
+                let length = lengths.get(u, j, edge);
+                let slack = (departure + length - self.dhat[v]).abs();
+                // NOTE: sources start their own paths, even when another source
+                // reaches them over a zero-weight edge
+                if slack <= tolerance * self.dhat[v].max(1.0) && !is_source(v) {
+                    dag[u].push(edge.clone());
+                    indegree[v] += 1;
+                }
+            }
+        }
+
+        // NOTE: Kahn's algorithm from the sources; vertices on or behind a
+        // tight zero-weight cycle never reach indegree zero
+        let mut counts = vec![0.0; n];
+        let mut queue = VecDeque::new();
+        for v in (0..n).filter(|&v| is_source(v)) {
+            counts[v] = 1.0;
+            queue.push_back(v);
+        }
+        let mut order = Vec::new();
+        while let Some(u) = queue.pop_front() {
+            order.push(u);
+            for edge in &dag[u] {
+                let v = *edge.vertex();
+                counts[v] += counts[u];
+                indegree[v] -= 1;
+                if indegree[v] == 0 {
+                    queue.push_back(v);
+                }
+            }
+        }
+        for (v, count) in counts.iter_mut().enumerate() {
+            if indegree[v] > 0 {
+                *count = f64::INFINITY;
+            }
+        }
+
+        Some(PathDag {
+            dag: Graph::from(dag),
+            counts,
+            order,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{filter::Filter, fixtures::grid, models::Edge};
+
+    #[test]
+    fn test_grid_counts_are_binomial() {
+        // 4x4 grid: only edges to the right and down are tight, so there are
+        // C(6, 3) = 20 paths corner to corner
+        let id = |x: usize, y: usize| y * 4 + x;
+        let mut graph = grid(4, |_, _| 1.5);
+        graph[0].push(Edge::new(15, 100.0));
+
+        let mut sp = ShortestPath::new(graph);
+        sp.get(0);
+        let dag = sp.path_dag(0.0).unwrap();
+        assert_eq!(dag.counts[15], 20.0);
+        assert_eq!(dag.counts[id(1, 1)], 2.0);
+        assert_eq!(dag.dag.iter().flatten().count(), 24);
+        assert_eq!(dag.order.len(), 16);
+    }
+
+    #[test]
+    fn test_tolerance_merges_float_ties() {
+        // 0.3 + 0.6 rounds above 0.9 in f32
+        let graph = vec![
+            vec![Edge::new(1, 0.3), Edge::new(2, 0.9)],
+            vec![Edge::new(2, 0.6)],
+            Vec::new(),
+            Vec::new(),
+        ];
+        let mut sp = ShortestPath::new(graph);
+        sp.get(0);
+        assert_eq!(sp.path_dag(0.0).unwrap().counts, [1.0, 1.0, 1.0, 0.0]);
+        assert_eq!(sp.path_dag(1e-6).unwrap().counts, [1.0, 1.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn test_zero_weight_cycle_is_infinite() {
+        let graph = vec![
+            vec![Edge::new(1, 1.0)],
+            vec![Edge::new(2, 0.0)],
+            vec![Edge::new(1, 0.0), Edge::new(3, 1.0)],
+            Vec::new(),
+        ];
+        let mut sp = ShortestPath::new(graph);
+        sp.get(0);
+        let dag = sp.path_dag(0.0).unwrap();
+        assert_eq!(dag.counts[0], 1.0);
+        assert!(dag.counts[1..].iter().all(|c| c.is_infinite()));
+        assert_eq!(dag.order, [0]);
+    }
+
+    #[test]
+    fn test_hop_counts_give_hop_dag() {
+        let graph = vec![
+            vec![Edge::new(1, 5.0), Edge::new(2, 20.0)],
+            vec![Edge::new(2, 5.0)],
+            Vec::new(),
+        ];
+        let mut sp = ShortestPath::new(graph).with_unit_weights(true);
+        assert_eq!(sp.get(0), [0.0, 1.0, 1.0]);
+        let dag = sp.path_dag(0.0).unwrap();
+        assert_eq!(dag.counts, [1.0, 1.0, 1.0]);
+        assert_eq!(dag.dag[0].len(), 2);
+        assert!(dag.dag[1].is_empty());
+    }
+
+    #[test]
+    fn test_sources_joined_by_zero_edge_count_once() {
+        // sources 0 and 1 both reach 2, and 0 -> 1 is tight
+        let graph = vec![
+            vec![Edge::new(1, 0.0), Edge::new(2, 1.0)],
+            vec![Edge::new(2, 1.0)],
+            Vec::new(),
+        ];
+        let mut sp = ShortestPath::new(graph);
+        sp.get_from(&[0, 1], None);
+        let dag = sp.path_dag(0.0).unwrap();
+        assert_eq!(dag.counts, [1.0, 1.0, 2.0]);
+        assert_eq!(dag.dag[0].len(), 1);
+        assert_eq!(dag.order.len(), 3);
+    }
+
+    #[test]
+    fn test_filtered_query_has_no_dag() {
+        let graph = vec![vec![Edge::new(1, 2.0)], Vec::new()];
+        let mut sp = ShortestPath::new(graph);
+        sp.get_from(&[0], Some(&Filter::new()));
+        assert!(sp.path_dag(0.0).is_none());
+        sp.get(0);
+        assert!(sp.path_dag(0.0).is_some());
+    }
+}
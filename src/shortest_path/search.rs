@@ -0,0 +1,63 @@
+use crate::{
+    heaps::IndexedHeap,
+    models::{Length, Vertex},
+};
+
+/// Scratch state of one point-to-point search. Only touched vertices are
+/// reset between queries, so repeated point queries do not pay O(n) each.
+#[derive(Debug, Default)]
+pub(crate) struct Search {
+    pub(crate) dist: Vec<Length>,
+    pub(crate) parent: Vec<Option<Vertex>>,
+    pub(crate) heap: IndexedHeap,
+    pub(crate) touched: Vec<Vertex>,
+    pub(crate) root: Vertex,
+}
+
+impl Search {
+    pub(crate) fn reset(&mut self, n: usize, root: Vertex) {
+        if self.dist.len() != n {
+            self.dist = vec![Length::INFINITY; n];
+            self.parent = vec![None; n];
+            self.heap = IndexedHeap::new(n);
+        }
+        for v in self.touched.drain(..) {
+            self.dist[v] = Length::INFINITY;
+            self.parent[v] = None;
+        }
+        self.heap.clear();
+
+        self.root = root;
+        self.dist[root] = 0.0;
+        self.touched.push(root);
+    }
+
+    /// Lowers the distance of `v` to `dist` through `u`; returns whether it improved.
+    pub(crate) fn relax(&mut self, u: Vertex, v: Vertex, dist: Length) -> bool {
+        if dist >= self.dist[v] {
+            return false;
+        }
+        if self.dist[v] == Length::INFINITY {
+            self.touched.push(v);
+        }
+        self.dist[v] = dist;
+        self.parent[v] = Some(u);
+        true
+    }
+
+    pub(crate) fn top(&self) -> Length {
+        self.heap
+            .peek()
+            .map_or(Length::INFINITY, |edge| *edge.length())
+    }
+
+    /// Tree path from `v` back to the root.
+    pub(crate) fn path(&self, mut v: Vertex) -> Vec<Vertex> {
+        let mut path = vec![v];
+        while let Some(u) = self.parent[v] {
+            path.push(u);
+            v = u;
+        }
+        path
+    }
+}
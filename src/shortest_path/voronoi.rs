@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use super::ShortestPath;
+use crate::models::{Length, Vertex};
+
+/// Result of [`ShortestPath::voronoi`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voronoi {
+    /// `(distance, nearest source)` per vertex; the source is `None` for
+    /// vertices no source reaches.
+    pub cells: Vec<(Length, Option<Vertex>)>,
+    /// Edges `u -> v` leaving the region of each source, i.e. with `u` in the
+    /// region and `v` in another one.
+    pub boundary: HashMap<Vertex, Vec<(Vertex, Vertex)>>,
+}
+
+impl Voronoi {
+    pub fn region(&self, v: Vertex) -> Option<Vertex> {
+        self.cells[v].1
+    }
+}
+
+impl ShortestPath {
+    /// Graph Voronoi partition: assigns every vertex to its closest source.
+    ///
+    /// A single multi-source run of [`Self::get_from`]: every relaxation that
+    /// strictly improves a vertex also passes on the source label of the
+    /// vertex it came from. Ties between sources go to whichever reached the
+    /// vertex first.
+    pub fn voronoi(&mut self, sources: &[Vertex]) -> Voronoi {
+        let distances = self.get_from(sources, None);
+        let labels = self.nearest_sources();
+
+        let mut boundary: HashMap<_, Vec<_>> = sources.iter().map(|&s| (s, Vec::new())).collect();
+        for (u, edges) in self.graph.iter().enumerate() {
+            let Some(region) = labels[u] else {
+                continue;
+            };
+            for edge in edges {
+                let v = *edge.vertex();
+                if labels[v] != Some(region) {
+                    boundary.entry(region).or_default().push((u, v));
+                }
+            }
+        }
+
+        Voronoi {
+            cells: distances.into_iter().zip(labels.iter().copied()).collect(),
+            boundary,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Edge;
+
+    #[test]
+    fn test_path_splits_between_sources() {
+        // 0 - 1 - 2 - 3 - 4 - 5, undirected, sources at both ends
+        let mut graph = vec![Vec::new(); 6];
+        for u in 0..5 {
+            graph[u].push(Edge::new(u + 1, 1.0));
+            graph[u + 1].push(Edge::new(u, 1.0));
+        }
+        graph[4][0] = Edge::new(3, 0.5);
+
+        let voronoi = ShortestPath::new(graph).voronoi(&[0, 5]);
+        let regions: Vec<_> = (0..6).map(|v| voronoi.region(v)).collect();
+        assert_eq!(
+            regions,
+            [Some(0), Some(0), Some(0), Some(5), Some(5), Some(5)]
+        );
+        assert_eq!(voronoi.cells[3], (1.5, Some(5)));
+        assert_eq!(voronoi.boundary[&0], vec![(2, 3)]);
+        assert_eq!(voronoi.boundary[&5], vec![(3, 2)]);
+    }
+
+    #[test]
+    fn test_boundary_lists_every_crossing_edge() {
+        //   0 - 1 - 2 - 3 - 4 - 5 - 6, plus chords 1 - 4 and 6 - 2
+        let mut graph = vec![Vec::new(); 7];
+        for (u, v, length) in [
+            (0, 1, 1.0),
+            (1, 2, 1.0),
+            (2, 3, 3.0),
+            (3, 4, 1.0),
+            (4, 5, 2.0),
+            (5, 6, 1.0),
+            (1, 4, 10.0),
+            (6, 2, 4.0),
+        ] {
+            graph[u].push(Edge::new(v, length));
+            graph[v].push(Edge::new(u, length));
+        }
+
+        let voronoi = ShortestPath::new(graph).voronoi(&[0, 3, 6]);
+        let regions: Vec<_> = (0..7).map(|v| voronoi.region(v)).collect();
+        let [a, b, c] = [Some(0), Some(3), Some(6)];
+        assert_eq!(regions, [a, a, a, b, b, c, c]);
+        assert_eq!(
+            voronoi.boundary,
+            HashMap::from([
+                (0, vec![(1, 4), (2, 3), (2, 6)]),
+                (3, vec![(3, 2), (4, 5), (4, 1)]),
+                (6, vec![(5, 4), (6, 2)]),
+            ])
+        );
+    }
+}
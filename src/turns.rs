@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use crate::{
+    ShortestPath,
+    models::{Edge, Graph, Length, Vertex, Weight},
+};
+
+/// Penalties and bans for turning from `a -> b` onto `b -> c`, keyed by
+/// `(a, b, c)`. Turns not in the table are free.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TurnCosts {
+    // None marks a forbidden turn
+    table: HashMap<(Vertex, Vertex, Vertex), Option<Length>>,
+}
+
+impl TurnCosts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, from: Vertex, via: Vertex, to: Vertex, cost: Weight) {
+        self.table.insert((from, via, to), Some(cost.get()));
+    }
+
+    pub fn forbid(&mut self, from: Vertex, via: Vertex, to: Vertex) {
+        self.table.insert((from, via, to), None);
+    }
+
+    /// Cost of the turn, `None` if it is forbidden.
+    pub fn get(&self, from: Vertex, via: Vertex, to: Vertex) -> Option<Length> {
+        self.table
+            .get(&(from, via, to))
+            .copied()
+            .unwrap_or(Some(0.0))
+    }
+}
+
+/// Shortest paths with turn costs, computed on the edge-based graph.
+///
+/// Every edge `e = a -> b` of the original graph becomes a vertex of the line
+/// graph, and every allowed turn onto `f = b -> c` an edge `e -> f` of length
+/// `turn cost + length(f)`. Each original vertex `s` also gets a start vertex
+/// with an edge of length `length(e)` to each of its out-edges `e`. The
+/// distance of `v` is then the smallest distance of an edge into `v`.
+#[derive(Debug)]
+pub struct TurnGraph {
+    n: usize,
+    // endpoints of original edge i, line graph vertex i
+    tails: Vec<Vertex>,
+    heads: Vec<Vertex>,
+    sp: ShortestPath,
+    // line graph distances of the last query
+    dist: Vec<Length>,
+    source: Option<Vertex>,
+}
+
+impl TurnGraph {
+    /// Builds the line graph.
+    pub fn new(graph: impl Into<Graph>, turns: &TurnCosts) -> Self {
+        let graph = graph.into();
+
+        let n = graph.len();
+        let (mut tails, mut heads, mut first) = (Vec::new(), Vec::new(), Vec::with_capacity(n + 1));
+        for (u, edges) in graph.iter().enumerate() {
+            first.push(tails.len());
+            for edge in edges {
+                tails.push(u);
+                heads.push(*edge.vertex());
+            }
+        }
+        first.push(tails.len());
+        let m = tails.len();
+        let length = |e: usize| *graph[tails[e]][e - first[tails[e]]].length();
+
+        let mut line = vec![Vec::new(); m + n];
+        for e in 0..m {
+            let (a, b) = (tails[e], heads[e]);
+            let range = first[b]..first[b + 1];
+            for (f, &c) in range.clone().zip(&heads[range]) {
+                if let Some(cost) = turns.get(a, b, c) {
+                    line[e].push(Edge::new(f, cost + length(f)));
+                }
+            }
+        }
+        for s in 0..n {
+            line[m + s] = (first[s]..first[s + 1])
+                .map(|e| Edge::new(e, length(e)))
+                .collect();
+        }
+
+        Self {
+            n,
+            tails,
+            heads,
+            sp: ShortestPath::new(line),
+            dist: Vec::new(),
+            source: None,
+        }
+    }
+
+    /// Distances from `s` to every vertex, turn costs included.
+    pub fn get(&mut self, s: Vertex) -> Vec<Length> {
+        let m = self.tails.len();
+        self.dist = self.sp.get(m + s);
+        self.source = Some(s);
+
+        let mut distances = vec![Length::INFINITY; self.n];
+        distances[s] = 0.0;
+        for (&v, &d) in self.heads.iter().zip(&self.dist) {
+            distances[v] = distances[v].min(d);
+        }
+        distances
+    }
+
+    /// Shortest path from the source of the last [`Self::get`] to `target`,
+    /// as original vertices.
+    ///
+    /// Returns `None` if `target` is unreachable or [`Self::get`] was never called.
+    pub fn path(&self, target: Vertex) -> Option<Vec<Vertex>> {
+        let s = self.source?;
+        if target == s {
+            return Some(vec![s]);
+        }
+        let last = (0..self.tails.len())
+            .filter(|&e| self.heads[e] == target && self.dist[e].is_finite())
+            .min_by(|&e, &f| self.dist[e].total_cmp(&self.dist[f]))?;
+
+        // the line graph path starts at the start vertex of s, then one
+        // original edge per step
+        let line = self.sp.path(last)?;
+        let mut path = vec![s];
+        path.extend(line[1..].iter().map(|&e| self.heads[e]));
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::random_grid;
+
+    //   0 - 1 - 2
+    //       |   |
+    //       3 - 4
+    fn junctions() -> Vec<Vec<Edge>> {
+        let mut graph = vec![Vec::new(); 5];
+        for (u, v) in [(0, 1), (1, 2), (1, 3), (2, 4), (3, 4)] {
+            graph[u].push(Edge::new(v, 1.0));
+            graph[v].push(Edge::new(u, 1.0));
+        }
+        graph
+    }
+
+    #[test]
+    fn test_without_turn_costs_matches_plain_graph() {
+        // non-unit lengths, so neither solver takes the BFS fast path
+        let side = 8;
+        let graph = random_grid(side, 46);
+
+        let mut turns = TurnGraph::new(graph.clone(), &TurnCosts::new());
+        let mut sp = ShortestPath::new(graph);
+        for s in 0..side * side {
+            assert_eq!(turns.get(s), sp.get(s), "source {s}");
+        }
+    }
+
+    #[test]
+    fn test_penalties_and_bans_reroute() {
+        let mut costs = TurnCosts::new();
+        for (u, edges) in junctions().iter().enumerate() {
+            for edge in edges {
+                costs.forbid(u, *edge.vertex(), u);
+            }
+        }
+        // turning left at 1 towards 3 is banned, the turn at 2 costs 5
+        costs.forbid(0, 1, 3);
+        costs.set(1, 2, 4, Weight::new(5.0).unwrap());
+        let mut turns = TurnGraph::new(junctions(), &costs);
+
+        // with the shortcut via 3 banned, 0 -> 1 -> 2 -> 4 costs 3 + 5
+        let distances = turns.get(0);
+        assert_eq!(distances[4], 8.0);
+        assert_eq!(turns.path(4), Some(vec![0, 1, 2, 4]));
+
+        costs.set(1, 2, 4, Weight::new(0.5).unwrap());
+        let mut turns = TurnGraph::new(junctions(), &costs);
+        assert_eq!(turns.get(0)[3], 4.5);
+        assert_eq!(turns.path(3), Some(vec![0, 1, 2, 4, 3]));
+        assert_eq!(turns.path(0), Some(vec![0]));
+    }
+}